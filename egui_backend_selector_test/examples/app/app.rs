@@ -28,6 +28,18 @@ impl egui_backend_selector::App for EguiApp {
             ui.label("Persistent String:");
             ui.text_edit_singleline(&mut self.data);
         });
+
+        let stats = backend.frame_stats();
+        let millis = |frame_time: Option<std::time::Duration>| frame_time.map_or_else(|| String::from("-"), |frame_time| format!("{:.2} ms", frame_time.as_secs_f64() * 1000.0));
+        egui::Area::new(egui::Id::new("frame_stats")).anchor(egui::Align2::RIGHT_TOP, [-8.0, 8.0]).show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(format!("Frame {}", stats.frame_count));
+                ui.label(format!("Last: {}", millis(stats.last_frame_time)));
+                ui.label(format!("Average: {}", millis(stats.average_frame_time)));
+                ui.label(format!("95th percentile: {}", millis(stats.percentile_95_frame_time)));
+                ui.label(format!("FPS: {}", stats.fps.map_or_else(|| String::from("-"), |fps| format!("{fps:.1}"))));
+            });
+        });
     }
 
     fn on_exit(&mut self) {
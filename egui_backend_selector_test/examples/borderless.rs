@@ -0,0 +1,50 @@
+use log::LevelFilter;
+use eframe::Storage;
+use egui_backend_selector::{Backend, BackendConfiguration, BackendInterop};
+
+/// Shows a window without title bar and borders, it is moved by dragging the custom title bar.
+/// The software backend cannot move the window, show the native title bar to move it there.
+/// Pass "software" or "eframe" as the first argument to force a backend.
+struct BorderlessApp {
+    decorations: bool,
+}
+
+impl egui_backend_selector::App for BorderlessApp {
+    fn update(&mut self, ctx: &egui::Context, mut backend: BackendInterop<'_>) {
+        egui::TopBottomPanel::top("title_bar").show(ctx, |ui| {
+            let title_bar = ui.interact(ui.max_rect(), egui::Id::new("title_bar"), egui::Sense::click_and_drag());
+            if title_bar.drag_started_by(egui::PointerButton::Primary) && !backend.start_window_drag() {
+                log::warn!("The backend cannot move the window");
+            }
+
+            ui.horizontal(|ui| {
+                ui.label(format!("Borderless window on {}", backend.backend_name()));
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("Close").clicked() {
+                        backend.request_exit();
+                    }
+                });
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label("Drag the title bar to move the window.");
+            if ui.checkbox(&mut self.decorations, "Show the native title bar").changed() {
+                backend.set_decorations(self.decorations);
+            }
+        });
+    }
+}
+
+fn main() {
+    _= trivial_log::init_std(LevelFilter::Trace);
+
+    if let Some(backend) = std::env::args().nth(1) {
+        let backend = backend.parse::<Backend>().expect("failed to parse backend");
+        egui_backend_selector::overwrite_backend(backend).expect("failed to overwrite backend");
+    }
+
+    let config = BackendConfiguration::default().with_decorations(false);
+    egui_backend_selector::run_app("egui-backend-selector-test", config, |_: egui::Context, _: Option<&dyn Storage>| BorderlessApp { decorations: false })
+        .expect("failed to run app");
+}
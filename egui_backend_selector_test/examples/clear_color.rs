@@ -0,0 +1,32 @@
+use log::LevelFilter;
+use eframe::Storage;
+use egui_backend_selector::{Backend, BackendConfiguration, BackendInterop};
+
+/// Shows the clear color behind an unfilled central panel, it should look the same on both backends.
+/// Pass "software" or "eframe" as the first argument to force a backend.
+struct ClearColorApp {}
+
+impl egui_backend_selector::App for ClearColorApp {
+    fn update(&mut self, ctx: &egui::Context, backend: BackendInterop<'_>) {
+        egui::CentralPanel::default().frame(egui::Frame::NONE).show(ctx, |ui| {
+            ui.label(format!("Running on {}", backend.backend_name()));
+            ui.label("The background is the clear color of the app.");
+        });
+    }
+
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        egui::Color32::from_rgb(90, 40, 120).to_normalized_gamma_f32()
+    }
+}
+
+fn main() {
+    _= trivial_log::init_std(LevelFilter::Trace);
+
+    if let Some(backend) = std::env::args().nth(1) {
+        let backend = backend.parse::<Backend>().expect("failed to parse backend");
+        egui_backend_selector::overwrite_backend(backend).expect("failed to overwrite backend");
+    }
+
+    egui_backend_selector::run_app("egui-backend-selector-test", BackendConfiguration::default(), |_: egui::Context, _: Option<&dyn Storage>| ClearColorApp {})
+        .expect("failed to run app");
+}
@@ -0,0 +1,61 @@
+use log::LevelFilter;
+use eframe::Storage;
+use egui_backend_selector::{Backend, BackendConfiguration, BackendInterop};
+
+/// Asks for confirmation before the window is closed.
+/// This only works on eframe, the software backend closes the window without asking the app.
+/// Pass "software" or "eframe" as the first argument to force a backend.
+struct CloseConfirmApp {
+    show_dialog: bool,
+}
+
+impl egui_backend_selector::App for CloseConfirmApp {
+    fn update(&mut self, ctx: &egui::Context, mut backend: BackendInterop<'_>) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Running on {}", backend.backend_name()));
+            if backend.backend() == Backend::Eframe {
+                ui.label("Close the window to see the confirmation dialog.");
+            } else {
+                ui.label("The software backend closes the window without a confirmation dialog.");
+            }
+        });
+
+        if !self.show_dialog {
+            return;
+        }
+
+        egui::Window::new("Unsaved changes")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Do you really want to exit?");
+                ui.horizontal(|ui| {
+                    if ui.button("Exit").clicked() {
+                        backend.request_exit();
+                    }
+
+                    if ui.button("Cancel").clicked() {
+                        self.show_dialog = false;
+                    }
+                });
+            });
+    }
+
+    fn on_close_requested(&mut self, _context: &egui::Context) -> bool {
+        self.show_dialog = true;
+        false
+    }
+}
+
+fn main() {
+    _= trivial_log::init_std(LevelFilter::Trace);
+
+    if let Some(backend) = std::env::args().nth(1) {
+        let backend = backend.parse::<Backend>().expect("failed to parse backend");
+        egui_backend_selector::overwrite_backend(backend).expect("failed to overwrite backend");
+    }
+
+    egui_backend_selector::run_app("egui-backend-selector-test", BackendConfiguration::default(), |_: egui::Context, _: Option<&dyn Storage>| CloseConfirmApp { show_dialog: false })
+        .expect("failed to run app");
+}
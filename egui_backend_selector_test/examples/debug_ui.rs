@@ -0,0 +1,38 @@
+use log::LevelFilter;
+use eframe::Storage;
+use egui_backend_selector::{Backend, BackendConfiguration, BackendInterop};
+
+/// Shows the debug ui of the backend selector in a window that is toggled with F12.
+/// Pass "software" or "eframe" as the first argument to force a backend.
+struct DebugUiApp {
+    show_debug_ui: bool,
+}
+
+impl egui_backend_selector::App for DebugUiApp {
+    fn update(&mut self, ctx: &egui::Context, mut backend: BackendInterop<'_>) {
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::F12)) {
+            self.show_debug_ui = !self.show_debug_ui;
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Running on {}", backend.backend_name()));
+            ui.label("Press F12 to toggle the debug window.");
+        });
+
+        egui::Window::new("Backend").open(&mut self.show_debug_ui).show(ctx, |ui| {
+            backend.show_debug_ui(ui);
+        });
+    }
+}
+
+fn main() {
+    _= trivial_log::init_std(LevelFilter::Trace);
+
+    if let Some(backend) = std::env::args().nth(1) {
+        let backend = backend.parse::<Backend>().expect("failed to parse backend");
+        egui_backend_selector::overwrite_backend(backend).expect("failed to overwrite backend");
+    }
+
+    egui_backend_selector::run_app("egui-backend-selector-test", BackendConfiguration::default(), |_: egui::Context, _: Option<&dyn Storage>| DebugUiApp { show_debug_ui: true })
+        .expect("failed to run app");
+}
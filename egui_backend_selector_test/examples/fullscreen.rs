@@ -0,0 +1,26 @@
+use log::LevelFilter;
+use eframe::Storage;
+use egui_backend_selector::{BackendConfiguration, BackendInterop};
+
+struct FullscreenApp {}
+
+impl egui_backend_selector::App for FullscreenApp {
+    fn update(&mut self, ctx: &egui::Context, mut backend: BackendInterop<'_>) {
+        let fullscreen = backend.is_fullscreen();
+        if ctx.input(|i| i.key_pressed(egui::Key::F11)) {
+            backend.set_fullscreen(!fullscreen);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Running on {}", backend.backend_name()));
+            ui.label(format!("Fullscreen: {fullscreen}, press F11 to toggle it."));
+        });
+    }
+}
+
+fn main() {
+    _= trivial_log::init_std(LevelFilter::Trace);
+
+    egui_backend_selector::run_app("egui-backend-selector-test", BackendConfiguration::default(), |_: egui::Context, _: Option<&dyn Storage>| FullscreenApp {})
+        .expect("failed to run app");
+}
@@ -0,0 +1,44 @@
+use log::LevelFilter;
+use eframe::Storage;
+use egui_backend_selector::{Backend, BackendConfiguration, BackendInterop};
+
+/// Saves a screenshot of the window as screenshot.png in the working directory when the button is pressed.
+/// Pass "software" or "eframe" as the first argument to force a backend.
+struct ScreenshotApp {
+    status: String,
+}
+
+impl egui_backend_selector::App for ScreenshotApp {
+    fn update(&mut self, ctx: &egui::Context, mut backend: BackendInterop<'_>) {
+        if let Some(screenshot) = backend.take_screenshot() {
+            let [width, height] = screenshot.size;
+            let pixels = screenshot.pixels.iter().flat_map(|pixel| pixel.to_array()).collect::<Vec<_>>();
+            self.status = match image::save_buffer("screenshot.png", &pixels, width as u32, height as u32, image::ColorType::Rgba8) {
+                Ok(()) => format!("Saved a {width}x{height} screenshot to screenshot.png"),
+                Err(e) => format!("Failed to save the screenshot: {e}"),
+            };
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Running on {}", backend.backend_name()));
+            if ui.button("Save screenshot").clicked() {
+                backend.request_screenshot();
+                self.status = String::from("Waiting for the screenshot");
+            }
+
+            ui.label(&self.status);
+        });
+    }
+}
+
+fn main() {
+    _= trivial_log::init_std(LevelFilter::Trace);
+
+    if let Some(backend) = std::env::args().nth(1) {
+        let backend = backend.parse::<Backend>().expect("failed to parse backend");
+        egui_backend_selector::overwrite_backend(backend).expect("failed to overwrite backend");
+    }
+
+    egui_backend_selector::run_app("egui-backend-selector-test", BackendConfiguration::default(), |_: egui::Context, _: Option<&dyn Storage>| ScreenshotApp { status: String::new() })
+        .expect("failed to run app");
+}
@@ -0,0 +1,56 @@
+use log::LevelFilter;
+use eframe::Storage;
+use egui_backend_selector::{Backend, BackendConfiguration, BackendInterop};
+
+/// Opens a secondary window from `update`. eframe shows it in its own native window,
+/// the software backend shows it embedded inside the main window.
+/// Pass "software" or "eframe" as the first argument to force a backend.
+struct SecondaryWindowApp {
+    show_secondary: bool,
+}
+
+impl egui_backend_selector::App for SecondaryWindowApp {
+    fn update(&mut self, ctx: &egui::Context, backend: BackendInterop<'_>) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Running on {}", backend.backend_name()));
+            ui.label(format!("Native secondary windows: {}", backend.supports_multiple_viewports()));
+            ui.checkbox(&mut self.show_secondary, "Show the secondary window");
+        });
+
+        if !self.show_secondary {
+            return;
+        }
+
+        backend.egui_context().show_viewport_immediate(
+            egui::ViewportId::from_hash_of("secondary"),
+            egui::ViewportBuilder::default().with_title("Secondary window").with_inner_size([300.0, 150.0]),
+            |ctx, class| {
+                if class == egui::ViewportClass::Embedded {
+                    egui::Window::new("Secondary window").show(ctx, |ui| {
+                        ui.label("This window is embedded in the main window.");
+                    });
+                } else {
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        ui.label("This is a native window.");
+                    });
+                }
+
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_secondary = false;
+                }
+            },
+        );
+    }
+}
+
+fn main() {
+    _= trivial_log::init_std(LevelFilter::Trace);
+
+    if let Some(backend) = std::env::args().nth(1) {
+        let backend = backend.parse::<Backend>().expect("failed to parse backend");
+        egui_backend_selector::overwrite_backend(backend).expect("failed to overwrite backend");
+    }
+
+    egui_backend_selector::run_app("egui-backend-selector-test", BackendConfiguration::default(), |_: egui::Context, _: Option<&dyn Storage>| SecondaryWindowApp { show_secondary: false })
+        .expect("failed to run app");
+}
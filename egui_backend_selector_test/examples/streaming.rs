@@ -0,0 +1,66 @@
+use log::LevelFilter;
+use eframe::Storage;
+use egui_backend_selector::{Backend, BackendConfiguration, BackendInterop};
+
+const WIDTH: usize = 640;
+const HEIGHT: usize = 480;
+
+/// Updates a 640x480 texture from cpu memory every frame, like a video player would.
+/// The frame time is shown to compare the cost of streaming on both backends.
+/// Pass "software" or "eframe" as the first argument to force a backend.
+struct StreamingApp {
+    texture: Option<egui::TextureHandle>,
+    frame: usize,
+}
+
+impl StreamingApp {
+    fn next_image(&mut self) -> egui::ColorImage {
+        self.frame = self.frame.wrapping_add(1);
+        let offset = self.frame;
+        let pixels = (0..WIDTH * HEIGHT)
+            .map(|index| {
+                let x = index % WIDTH;
+                let y = index / WIDTH;
+                egui::Color32::from_rgb((x + offset) as u8, (y + offset) as u8, ((x ^ y) + offset) as u8)
+            })
+            .collect();
+
+        egui::ColorImage::new([WIDTH, HEIGHT], pixels)
+    }
+}
+
+impl egui_backend_selector::App for StreamingApp {
+    fn update(&mut self, ctx: &egui::Context, backend: BackendInterop<'_>) {
+        let image = self.next_image();
+        match self.texture.as_mut() {
+            Some(texture) => texture.set(image, egui::TextureOptions::LINEAR),
+            None => self.texture = Some(backend.register_texture("stream", image, egui::TextureOptions::LINEAR)),
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Running on {}", backend.backend_name()));
+            match backend.last_frame_time() {
+                Some(frame_time) => ui.label(format!("Frame time: {:.2} ms", frame_time.as_secs_f64() * 1000.0)),
+                None => ui.label("Frame time: unknown"),
+            };
+
+            if let Some(texture) = self.texture.as_ref() {
+                ui.image((texture.id(), texture.size_vec2()));
+            }
+        });
+
+        ctx.request_repaint();
+    }
+}
+
+fn main() {
+    _= trivial_log::init_std(LevelFilter::Trace);
+
+    if let Some(backend) = std::env::args().nth(1) {
+        let backend = backend.parse::<Backend>().expect("failed to parse backend");
+        egui_backend_selector::overwrite_backend(backend).expect("failed to overwrite backend");
+    }
+
+    egui_backend_selector::run_app("egui-backend-selector-test", BackendConfiguration::default(), |_: egui::Context, _: Option<&dyn Storage>| StreamingApp { texture: None, frame: 0 })
+        .expect("failed to run app");
+}
@@ -0,0 +1,104 @@
+use log::LevelFilter;
+use eframe::egui_wgpu::{self, wgpu};
+use eframe::Storage;
+use egui_backend_selector::{Backend, BackendConfiguration, BackendInterop};
+
+/// Draws a triangle with a custom wgpu pipeline when eframe uses the wgpu renderer.
+/// The software backend has no wgpu render state, there a fallback is painted with egui shapes.
+/// Pass "software" or "eframe" as the first argument to force a backend.
+struct WgpuTriangleApp {
+    pipeline_created: bool,
+}
+
+const SHADER: &str = r"
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let x = f32(i32(index) - 1) * 0.8;
+    let y = select(-0.8, 0.8, index == 1u);
+    return vec4<f32>(x, y, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.9, 0.4, 0.1, 1.0);
+}
+";
+
+/// Paints the triangle with the pipeline stored in the callback resources.
+struct TriangleCallback;
+
+impl egui_wgpu::CallbackTrait for TriangleCallback {
+    fn paint(&self, _info: egui::PaintCallbackInfo, render_pass: &mut wgpu::RenderPass<'static>, callback_resources: &egui_wgpu::CallbackResources) {
+        if let Some(pipeline) = callback_resources.get::<wgpu::RenderPipeline>() {
+            render_pass.set_pipeline(pipeline);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+fn create_pipeline(render_state: &egui_wgpu::RenderState) {
+    let shader = render_state.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("triangle"),
+        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+    });
+
+    let pipeline = render_state.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("triangle"),
+        layout: None,
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(render_state.target_format.into())],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    });
+
+    render_state.renderer.write().callback_resources.insert(pipeline);
+}
+
+impl egui_backend_selector::App for WgpuTriangleApp {
+    fn update(&mut self, ctx: &egui::Context, mut backend: BackendInterop<'_>) {
+        let render_state = backend.wgpu_render_state();
+        if !self.pipeline_created && let Some(render_state) = render_state {
+            create_pipeline(render_state);
+            self.pipeline_created = true;
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Running on {}", backend.backend_name()));
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(300.0, 300.0), egui::Sense::hover());
+            backend.add_paint_callback(ui.painter(), egui_wgpu::Callback::new_paint_callback(rect, TriangleCallback), |painter, rect| {
+                let points = vec![rect.center_bottom() - egui::vec2(rect.width() * 0.4, rect.height() * 0.1), rect.center_top() + egui::vec2(0.0, rect.height() * 0.1), rect.center_bottom() + egui::vec2(rect.width() * 0.4, -rect.height() * 0.1)];
+                painter.add(egui::Shape::convex_polygon(points, egui::Color32::from_rgb(230, 102, 26), egui::Stroke::NONE));
+            });
+        });
+    }
+}
+
+fn main() {
+    _= trivial_log::init_std(LevelFilter::Trace);
+
+    if let Some(backend) = std::env::args().nth(1) {
+        let backend = backend.parse::<Backend>().expect("failed to parse backend");
+        egui_backend_selector::overwrite_backend(backend).expect("failed to overwrite backend");
+    }
+
+    let config = BackendConfiguration::from(eframe::NativeOptions {
+        renderer: eframe::Renderer::Wgpu,
+        ..eframe::NativeOptions::default()
+    });
+
+    egui_backend_selector::run_app("egui-backend-selector-test", config, |_: egui::Context, _: Option<&dyn Storage>| WgpuTriangleApp { pipeline_created: false })
+        .expect("failed to run app");
+}
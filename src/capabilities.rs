@@ -0,0 +1,24 @@
+/// Describes which features are available with the active backend and the enabled feature flags.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+#[allow(clippy::struct_excessive_bools)]
+pub struct BackendCapabilities {
+    /// `BackendInterop::gl` returns the opengl context.
+    pub has_glow: bool,
+
+    /// `BackendInterop::wgpu_render_state` returns the wgpu render state.
+    pub has_wgpu: bool,
+
+    /// `BackendInterop::storage` returns a storage that is persisted between runs.
+    pub has_persistent_storage: bool,
+
+    /// `BackendInterop::window_handle` and `BackendInterop::display_handle` return the native handles.
+    /// Only eframe provides them, this is always false with the software backend, custom backends and on the web.
+    pub has_raw_window_handle: bool,
+
+    /// `BackendInterop::clipboard_text` and `BackendInterop::set_clipboard_text` access the clipboard of the os.
+    pub has_clipboard: bool,
+
+    /// `App::on_file_drop` and `App::on_file_hover` are called when files are dragged onto the window.
+    pub has_file_drop: bool,
+}
@@ -0,0 +1,42 @@
+use crate::Backend;
+
+/// Name of the command line argument that selects the backend.
+const BACKEND_ARG: &str = "--egui-backend";
+
+/// Returns the backend selected by the `--egui-backend=<value>` or `--egui-backend <value>` argument.
+///
+/// If the argument is given more than once, the last valid value wins. Unknown values are logged and ignored.
+/// Arguments after `--` are not considered.
+///
+/// The arguments are neither consumed nor reordered, so this composes with other argument parsers.
+pub fn backend_from_args(mut args: impl Iterator<Item = String>) -> Option<Backend> {
+    let mut backend = None;
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+
+        let value = if arg == BACKEND_ARG {
+            args.next()
+        } else if let Some(value) = arg
+            .strip_prefix(BACKEND_ARG)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            Some(value.to_string())
+        } else {
+            continue;
+        };
+
+        let Some(value) = value else {
+            log::warn!("{BACKEND_ARG} is missing a value, ignoring it.");
+            break;
+        };
+
+        match value.parse::<Backend>() {
+            Ok(parsed) => backend = Some(parsed),
+            Err(e) => log::warn!("Ignoring {BACKEND_ARG}: {e}"),
+        }
+    }
+
+    backend
+}
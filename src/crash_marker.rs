@@ -0,0 +1,53 @@
+use crate::Backend;
+use std::path::{Path, PathBuf};
+
+/// Name of the marker file inside the storage directory of the app.
+const CRASH_MARKER_FILE_NAME: &str = "backend_selector_running.marker";
+
+/// Returns the path of the marker file in the storage directory of the app.
+fn marker_path(storage_dir: &Path) -> PathBuf {
+    storage_dir.join(CRASH_MARKER_FILE_NAME)
+}
+
+/// Returns the backend recorded by a previous run of the app that did not exit cleanly.
+pub fn read(storage_dir: &Path) -> Option<Backend> {
+    std::fs::read_to_string(marker_path(storage_dir))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Records that the app is running with the given backend.
+pub fn write(storage_dir: &Path, backend: Backend) {
+    let path = marker_path(storage_dir);
+
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        _ = std::fs::create_dir_all(parent);
+    }
+
+    if let Err(e) = std::fs::write(&path, backend.to_string()) {
+        log::warn!(
+            "Failed to write crash marker. Could not write file {} err={e}",
+            path.display()
+        );
+    }
+}
+
+/// Removes the marker, the app exited cleanly.
+pub fn clear(storage_dir: &Path) {
+    let path = marker_path(storage_dir);
+
+    if !path.exists() {
+        return;
+    }
+
+    if let Err(e) = std::fs::remove_file(&path) {
+        log::warn!(
+            "Failed to remove crash marker. Could not delete file {} err={e}",
+            path.display()
+        );
+    }
+}
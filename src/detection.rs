@@ -0,0 +1,1315 @@
+use crate::report::report;
+use crate::{
+    Backend, BackendProbeResult, BackendSelectionReason, BackendSelectionReport, PlatformDetector,
+    RealPlatformDetector,
+};
+#[cfg(windows)]
+use main_thread::IsMainThread;
+#[cfg(any(windows, target_os = "linux"))]
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+/// A probe registered by the application.
+type CustomProbe = Box<dyn Fn() -> Option<Backend> + Send>;
+
+/// Probes registered by the application, in the order they were registered.
+static CUSTOM_PROBES: Mutex<Vec<CustomProbe>> = Mutex::new(Vec::new());
+
+/// Environment variable that tells the process that it was only spawned to probe opengl.
+const PROBE_ENV_VAR: &str = "__EGUI_BACKEND_SELECTOR_PROBE";
+
+/// Exit code of the probe process if eframe should be used.
+const PROBE_EXIT_CODE_EFRAME: i32 = 64;
+
+/// Exit code of the probe process if the software backend should be used.
+const PROBE_EXIT_CODE_SOFTWARE_BACKEND: i32 = 65;
+
+/// Exit code of the probe process if opengl is only provided by a software renderer.
+const PROBE_EXIT_CODE_SOFTWARE_GL: i32 = 66;
+
+/// Value of the probe environment variable if software opengl renderers should not be detected.
+#[cfg(windows)]
+const PROBE_ALLOW_SOFTWARE_GL: &str = "allow-software-gl";
+
+/// Environment variable that tells the probe process the minimum opengl version, formatted as major.minor.
+#[cfg(windows)]
+const PROBE_OPENGL_VERSION_ENV_VAR: &str = "__EGUI_BACKEND_SELECTOR_PROBE_OPENGL_VERSION";
+
+/// The minimum opengl version eframe appears to need.
+pub const DEFAULT_MINIMUM_OPENGL_VERSION: (u8, u8) = (3, 2);
+
+/// Environment variable that forces the software backend.
+/// It is set when the process is relaunched because eframe failed to initialize or the opengl probe hung.
+pub const FALLBACK_ENV_VAR: &str = "__EGUI_BACKEND_SELECTOR_FALLBACK";
+
+/// Options that affect how the backend is determined.
+#[derive(Clone)]
+pub struct DetectionOptions {
+    /// Directory where the result of the opengl probe is cached. None disables caching.
+    pub probe_cache_path: Option<std::path::PathBuf>,
+
+    /// Perform the opengl probe in a child process.
+    pub isolated_probe: bool,
+
+    /// Ignore the cached result of the opengl probe. The new result is still cached.
+    pub force_reprobe: bool,
+
+    /// Use eframe even if opengl is only provided by a software renderer like llvmpipe.
+    /// The renderer is only known on Windows, where the opengl probe creates a context.
+    pub allow_software_gl: bool,
+
+    /// How long the opengl probe may take before the graphics driver is assumed to hang.
+    pub probe_timeout: Duration,
+
+    /// The opengl version the probe requires for eframe to be selected, as major and minor version.
+    pub minimum_opengl_version: (u8, u8),
+
+    /// Queries the environment, the file system and opengl for the built-in checks.
+    pub platform_detector: Arc<dyn PlatformDetector>,
+}
+
+impl Default for DetectionOptions {
+    fn default() -> Self {
+        Self {
+            probe_cache_path: None,
+            isolated_probe: false,
+            force_reprobe: false,
+            allow_software_gl: false,
+            probe_timeout: Duration::from_secs(5),
+            minimum_opengl_version: DEFAULT_MINIMUM_OPENGL_VERSION,
+            platform_detector: Arc::new(RealPlatformDetector),
+        }
+    }
+}
+
+/// Registers a probe that is run before the built-in detection.
+///
+/// The probes are run in the order they were registered, the first probe that returns a backend wins.
+/// If all probes return None, then the built-in detection decides.
+/// Probes only run once, when the backend is first selected by `get_backend` or `run_app`,
+/// so they must be registered before that. Probes must not call `register_probe` themselves.
+///
+/// Note that probes run on whatever thread first selects the backend.
+/// On Windows the built-in detection only works on the main thread,
+/// so `get_backend` might still return None outside the main thread if all probes return None.
+pub fn register_probe(probe: impl Fn() -> Option<Backend> + Send + 'static) {
+    CUSTOM_PROBES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Box::new(probe));
+}
+
+/// Runs the probes registered by the application.
+/// Returns the report of the first probe that selected a backend.
+pub fn run_custom_probes() -> Option<BackendSelectionReport> {
+    let probes = CUSTOM_PROBES.lock().unwrap_or_else(PoisonError::into_inner);
+    probes.iter().enumerate().find_map(|(index, probe)| {
+        probe().map(|backend| {
+            report(
+                backend,
+                BackendSelectionReason::CustomProbe,
+                format!("probe #{index}"),
+            )
+        })
+    })
+}
+
+/// Performs the opengl probe and exits the process if the process was spawned to perform the probe.
+/// This does nothing if the process was not spawned to perform the probe.
+///
+/// This is only needed if `BackendConfiguration::with_isolated_probe` is enabled.
+/// It should be called at the very top of the main function.
+/// `run_app` calls this function too, but everything your main function does before calling `run_app`
+/// would also be done by the probe process.
+pub fn handle_probe_subprocess() {
+    let Some(value) = std::env::var_os(PROBE_ENV_VAR) else {
+        return;
+    };
+
+    #[cfg(windows)]
+    let probe_report = probe_opengl(
+        value == PROBE_ALLOW_SOFTWARE_GL,
+        std::env::var(PROBE_OPENGL_VERSION_ENV_VAR)
+            .ok()
+            .and_then(|version| parse_opengl_version(&version))
+            .unwrap_or(DEFAULT_MINIMUM_OPENGL_VERSION),
+    );
+
+    #[cfg(not(windows))]
+    let probe_report = {
+        _ = value;
+        report(
+            Backend::Eframe,
+            BackendSelectionReason::Default,
+            String::new(),
+        )
+    };
+
+    std::process::exit(match (probe_report.backend, probe_report.reason) {
+        (_, BackendSelectionReason::SoftwareGlRenderer) => PROBE_EXIT_CODE_SOFTWARE_GL,
+        (Backend::SoftwareBackend, _) => PROBE_EXIT_CODE_SOFTWARE_BACKEND,
+        //The built-in detection never selects a custom backend.
+        _ => PROBE_EXIT_CODE_EFRAME,
+    });
+}
+
+/// Parses an opengl version formatted as major.minor.
+#[cfg(windows)]
+fn parse_opengl_version(version: &str) -> Option<(u8, u8)> {
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// Runs the built-in checks for every backend, ordered by preference.
+/// Returns None if the checks cannot run on the current thread.
+fn probe_all(options: &DetectionOptions) -> Option<Vec<BackendProbeResult>> {
+    let eframe = probe_eframe(options)?;
+
+    Some(vec![
+        BackendProbeResult {
+            backend: Backend::Eframe,
+            available: eframe.backend == Backend::Eframe,
+            reason: eframe.reason,
+            detail: eframe.detail,
+        },
+        BackendProbeResult {
+            backend: Backend::SoftwareBackend,
+            available: true,
+            reason: BackendSelectionReason::Default,
+            detail: "the software backend works everywhere".to_string(),
+        },
+    ])
+}
+
+/// Runs the built-in checks for every backend without selecting a backend or launching anything.
+///
+/// This is meant for diagnostic tools that want to show which backends would work on the system.
+/// The results are ordered by preference, the first available backend is the one `get_backend` would select.
+/// Probes registered with `register_probe` are not run and the result of the opengl probe is not cached.
+///
+/// On Windows this must be called on the main thread, like `get_backend`.
+/// On other threads eframe is reported as unavailable, since the opengl probe cannot run there.
+#[must_use]
+pub fn probe_backends() -> Vec<BackendProbeResult> {
+    probe_all(&DetectionOptions::default()).unwrap_or_else(|| {
+        vec![
+            BackendProbeResult {
+                backend: Backend::Eframe,
+                available: false,
+                reason: BackendSelectionReason::Default,
+                detail: "the checks for eframe must run on the main thread".to_string(),
+            },
+            BackendProbeResult {
+                backend: Backend::SoftwareBackend,
+                available: true,
+                reason: BackendSelectionReason::Default,
+                detail: "the software backend works everywhere".to_string(),
+            },
+        ]
+    })
+}
+
+/// Selects the first available backend of the built-in checks.
+/// The reason why a more preferred backend is unavailable is reported as the reason of the selection.
+/// Returns None if the checks cannot run on the current thread.
+pub fn determine_backend(options: &DetectionOptions) -> Option<BackendSelectionReport> {
+    let mut skipped: Option<BackendProbeResult> = None;
+    for probe in probe_all(options)? {
+        if probe.available {
+            let (reason, detail) = skipped.map_or((probe.reason, probe.detail), |skipped| {
+                (skipped.reason, skipped.detail)
+            });
+            return Some(report(probe.backend, reason, detail));
+        }
+
+        skipped.get_or_insert(probe);
+    }
+
+    None
+}
+
+/// macOS-specific checks whether eframe works.
+/// If it does not, then the report names the software backend and the reason.
+#[cfg(target_os = "macos")]
+#[allow(clippy::unnecessary_wraps)]
+fn probe_eframe(options: &DetectionOptions) -> Option<BackendSelectionReport> {
+    _ = options;
+
+    if crate::platform::is_remote_display_session() {
+        //Screen Sharing or Apple Remote Desktop session that is not shown on the physical display.
+        return Some(report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::RemoteDesktopSession,
+            "session is not on the console",
+        ));
+    }
+
+    //A VM alone is not a reason to avoid eframe, Parallels guests with Metal acceleration work fine.
+    if crate::platform::is_virtual_machine().is_some() && !crate::macos::has_accelerated_gl() {
+        return Some(report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::VmWithoutGlAcceleration,
+            "no hardware accelerated opengl 3.2 pixel format",
+        ));
+    }
+
+    Some(report(
+        Backend::Eframe,
+        BackendSelectionReason::Default,
+        String::new(),
+    ))
+}
+
+/// FreeBSD and OpenBSD specific checks whether eframe works.
+/// Remote X11 displays are detected like on linux, otherwise eframe is used.
+#[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
+#[allow(clippy::unnecessary_wraps)]
+fn probe_eframe(options: &DetectionOptions) -> Option<BackendSelectionReport> {
+    let detector = options.platform_detector.as_ref();
+    if let Some(report) = detect_remote_session(detector) {
+        return Some(report);
+    }
+
+    let detail = detector.display_env().map_or_else(
+        || "DISPLAY is not set".to_string(),
+        |display| format!("DISPLAY={display}"),
+    );
+
+    Some(report(
+        Backend::Eframe,
+        BackendSelectionReason::Default,
+        detail,
+    ))
+}
+
+/// Checks whether eframe works on Not windows, not linux, not macOS and not FreeBSD or OpenBSD. (Basically eframe works everytime)
+#[cfg(all(
+    not(windows),
+    not(target_os = "linux"),
+    not(target_os = "macos"),
+    not(target_os = "freebsd"),
+    not(target_os = "openbsd")
+))]
+#[allow(clippy::unnecessary_wraps)]
+fn probe_eframe(options: &DetectionOptions) -> Option<BackendSelectionReport> {
+    _ = options;
+    //Other BSDs.
+    Some(report(
+        Backend::Eframe,
+        BackendSelectionReason::Default,
+        String::new(),
+    ))
+}
+
+/// Linux-specific checks whether eframe works.
+/// If it does not, then the report names the software backend and the reason.
+#[cfg(target_os = "linux")]
+#[allow(clippy::unnecessary_wraps)]
+fn probe_eframe(options: &DetectionOptions) -> Option<BackendSelectionReport> {
+    //We care about remote display sessions here, because eframe performs poorly on those, and about systems without a gpu.
+    let detector = options.platform_detector.as_ref();
+
+    if let Some(report) = detect_wsl(detector) {
+        return Some(report);
+    }
+
+    if let Some(report) = detect_remote_session(detector) {
+        return Some(report);
+    }
+
+    if let Some(report) = detect_waypipe(detector) {
+        return Some(report);
+    }
+
+    if let Some(report) = probe_linux_gl(detector) {
+        return Some(report);
+    }
+
+    let detail = detector.display_env().map_or_else(
+        || "DISPLAY is not set".to_string(),
+        |display| format!("DISPLAY={display}"),
+    );
+
+    Some(report(
+        Backend::Eframe,
+        BackendSelectionReason::Default,
+        detail,
+    ))
+}
+
+/// Detects remote display sessions from the environment variables returned by the detector.
+/// Returns a report selecting the software backend for a remote session, None for a local session.
+///
+/// Each detected session has its own reason in the report.
+/// To use eframe in one of these sessions anyway, register a probe with `register_probe` or call `overwrite_backend`.
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
+pub fn detect_remote_session(detector: &dyn PlatformDetector) -> Option<BackendSelectionReport> {
+    //These sessions provide a display that looks local, but everything is rendered on the server and sent over the network.
+    let session_vars = [
+        ("XRDP_SESSION", BackendSelectionReason::XrdpSession),
+        //x2go is based on NX, so it is checked before NoMachine.
+        ("X2GO_SESSION", BackendSelectionReason::X2goSession),
+        ("NX_CLIENT", BackendSelectionReason::NoMachineSession),
+        ("VNCDESKTOP", BackendSelectionReason::VncSession),
+        (
+            "CHROME_REMOTE_DESKTOP_SESSION",
+            BackendSelectionReason::ChromeRemoteDesktopSession,
+        ),
+    ];
+
+    for (name, reason) in session_vars {
+        if let Some(value) = detector.env_var(name) {
+            return Some(report(
+                Backend::SoftwareBackend,
+                reason,
+                format!("{name}={value}"),
+            ));
+        }
+    }
+
+    //No DISPLAY means we are not on X11, must be wayland where eframe works unless it's waypipe.
+    let display = detector.display_env()?;
+    if display.starts_with(':') || display.contains("/unix:") {
+        return None;
+    }
+
+    //This is remote X11 session. OpenGL will be the slowest thing in the universe.
+    //ssh -X and -Y forward the display to something like localhost:10.0
+    let reason = if detector.env_var("SSH_CONNECTION").is_some()
+        || detector.env_var("SSH_CLIENT").is_some()
+    {
+        BackendSelectionReason::SshForwarding
+    } else {
+        BackendSelectionReason::RemoteX11Display
+    };
+
+    Some(report(
+        Backend::SoftwareBackend,
+        reason,
+        format!("DISPLAY={display}"),
+    ))
+}
+
+/// Detects the windows subsystem for linux.
+/// Returns None if this is not WSL.
+///
+/// `WSLg` provides a gpu through `/dev/dxg` instead of `/dev/dri`, so eframe is selected without probing.
+/// Without `WSLg` the display is an X server on windows that is forwarded over the network, and WSL1 has no gpu access at all.
+#[cfg(target_os = "linux")]
+fn detect_wsl(detector: &dyn PlatformDetector) -> Option<BackendSelectionReport> {
+    let version = detector
+        .read_file(Path::new("/proc/version"))
+        .unwrap_or_default()
+        .to_lowercase();
+
+    if !version.contains("microsoft")
+        && !detector.file_exists(Path::new("/proc/sys/fs/binfmt_misc/WSLInterop"))
+    {
+        return None;
+    }
+
+    //The WSL2 kernel is called something like 5.15.153.1-microsoft-standard-WSL2, the WSL1 "kernel" is just Microsoft.
+    if version.contains("microsoft")
+        && !version.contains("microsoft-standard")
+        && !version.contains("wsl2")
+    {
+        return Some(report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::Wsl1,
+            "/proc/version does not mention WSL2",
+        ));
+    }
+
+    if detector.env_var("WAYLAND_DISPLAY").is_some() && detector.file_exists(Path::new("/mnt/wslg"))
+    {
+        return Some(report(
+            Backend::Eframe,
+            BackendSelectionReason::Wslg,
+            "WAYLAND_DISPLAY is set and /mnt/wslg exists",
+        ));
+    }
+
+    Some(report(
+        Backend::SoftwareBackend,
+        BackendSelectionReason::WslWithoutWslg,
+        "/mnt/wslg does not exist or WAYLAND_DISPLAY is not set",
+    ))
+}
+
+/// Detects wayland sessions forwarded with waypipe.
+/// Returns a report selecting the software backend for a waypipe session, None otherwise.
+///
+/// waypipe starts the application as its child process, so the ancestors of this process are checked.
+/// If there is no waypipe process among them, then the session is assumed to be local.
+#[cfg(target_os = "linux")]
+pub fn detect_waypipe(detector: &dyn PlatformDetector) -> Option<BackendSelectionReport> {
+    if detector.env_var("WAYLAND_DISPLAY").is_none() && detector.env_var("WAYLAND_SOCKET").is_none()
+    {
+        return None;
+    }
+
+    if let Some(name) = detector
+        .env_var_names()
+        .into_iter()
+        .find(|name| name.starts_with("WAYPIPE_"))
+    {
+        return Some(report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::WaypipeSession,
+            format!("{name} is set"),
+        ));
+    }
+
+    //Limits how far up the process tree we look, in case /proc contains something unexpected.
+    let mut pid = String::from("self");
+    for _ in 0..16 {
+        //The process name in the stat file is in parentheses and may contain spaces, the parent pid follows it.
+        let stat = detector.read_file(Path::new(&format!("/proc/{pid}/stat")))?;
+        let (_, after_name) = stat.rsplit_once(')')?;
+        let parent = after_name.split_whitespace().nth(1)?;
+        if parent == "0" || parent == "1" {
+            return None;
+        }
+
+        let comm = detector.read_file(Path::new(&format!("/proc/{parent}/comm")))?;
+        if comm.trim_end() == "waypipe" {
+            return Some(report(
+                Backend::SoftwareBackend,
+                BackendSelectionReason::WaypipeSession,
+                format!("parent process {parent} is waypipe"),
+            ));
+        }
+
+        pid = parent.to_string();
+    }
+
+    None
+}
+
+/// Checks if the system can provide hardware accelerated opengl.
+/// Returns a report selecting the software backend if it cannot, None if opengl looks usable.
+///
+/// This only checks for a gpu render node and the opengl libraries, no context is created.
+/// Nearly all linux distros come with mesa llvm-pipe, which would make opengl "work" without a gpu,
+/// but the software backend performs better than llvm-pipe.
+/// Without a context the `GL_RENDERER` cannot be queried, so mesa falling back to llvm-pipe on a machine
+/// with a render node, e.g. because the driver does not support the gpu, is not detected.
+/// Creating a context here would risk the same driver hangs the Windows probe needs its watchdog for.
+#[cfg(target_os = "linux")]
+fn probe_linux_gl(detector: &dyn PlatformDetector) -> Option<BackendSelectionReport> {
+    let has_render_node = detector
+        .dir_entries(Path::new("/dev/dri"))
+        .is_some_and(|entries| entries.iter().any(|name| name.starts_with("renderD")));
+
+    if !has_render_node {
+        return Some(report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::NoGpuRenderNode,
+            "no /dev/dri/renderD* node",
+        ));
+    }
+
+    //eframe uses EGL on wayland and either EGL or GLX on X11.
+    let has_gl_library = ["libEGL.so.1", "libGL.so.1"]
+        .into_iter()
+        .any(|name| detector.has_library(name));
+
+    if !has_gl_library {
+        return Some(report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::GlLibraryMissing,
+            "neither libEGL.so.1 nor libGL.so.1 could be loaded",
+        ));
+    }
+
+    None
+}
+
+/// Windows-specific checks whether eframe works.
+/// If it does not, then the report names the software backend and the reason.
+/// Returns None if not called on the main thread.
+#[cfg(windows)]
+fn probe_eframe(options: &DetectionOptions) -> Option<BackendSelectionReport> {
+    if IsMainThread::OtherThread == main_thread::is_main_thread() {
+        return None;
+    }
+
+    let detector = options.platform_detector.as_ref();
+
+    if crate::platform::is_remote_display_session() {
+        //Technically, we could query some obscure registry keys here,
+        //as well as some group policies. It is technically possible to enable opengl 3.2 via RDP,
+        //however, it is so poorly documented by microsoft that I only managed to do it once by accident and could never reproduce it.
+        //Needless to say, if it's an RDP connection, then we just use the software renderer.
+        return Some(report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::RemoteDesktopSession,
+            "SM_REMOTESESSION is set",
+        ));
+    }
+
+    //We dont need to check this on aarch64 as I am pretty sure that only KVM supports this properly and the virtio drivers for it actually work with eframe.
+    let adapters = crate::probe_cache::active_display_adapters();
+    if let Some(report) = classify_display_adapters(&adapters) {
+        return Some(report);
+    }
+
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    {
+        if let Some(vm_type) = crate::platform::is_virtual_machine() {
+            //We are running on a VM.
+
+            // The dll checks are only a fallback for when the display adapters could not be enumerated.
+            // These checks cover sensible use cases.
+            // I.e., They assume that it's unlikely someone migrated a VM from VirtualBox to KVM,
+            // without first uninstalling all the VirtualBox drivers.
+
+            //Process will segfault if we try eframe. This is the VMWare 3d driver. It's not good enough.
+            if adapters.is_empty()
+                && detector.file_exists(Path::new("C:\\Windows\\System32\\vm3dgl64.dll"))
+            {
+                return Some(report(
+                    Backend::SoftwareBackend,
+                    BackendSelectionReason::VmwareDriverPresent,
+                    "C:\\Windows\\System32\\vm3dgl64.dll exists",
+                ));
+            }
+
+            //Eframe will fail to launch due to missing gl extensions. This is the Virtualbox opengl driver.
+            if adapters.is_empty()
+                && detector.file_exists(Path::new("C:\\Windows\\System32\\VBoxGL.dll"))
+            {
+                return Some(report(
+                    Backend::SoftwareBackend,
+                    BackendSelectionReason::VirtualBoxDriverPresent,
+                    "C:\\Windows\\System32\\VBoxGL.dll exists",
+                ));
+            }
+
+            //The Hyper-V video adapter has no usable opengl 3.2, the probe sometimes half-succeeds and eframe renders garbage.
+            //If a gpu was passed through with DDA, then it is an active adapter too and the probe decides.
+            //Enhanced sessions are RDP sessions, which are already handled above.
+            if vm_type == crate::platform::VmType::HyperV
+                && !adapters.is_empty()
+                && adapters.iter().all(|adapter| {
+                    adapter.name.contains("Hyper-V Video") || adapter.name.contains("Basic Display")
+                })
+            {
+                return Some(report(
+                    Backend::SoftwareBackend,
+                    BackendSelectionReason::HyperVVideoAdapter,
+                    adapters
+                        .iter()
+                        .map(|adapter| adapter.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ));
+            }
+
+            //Probably KVM? if so theres a high chance that eframe works.
+        }
+    }
+
+    let (major, minor) = options.minimum_opengl_version;
+    if let Some(available) = detector.create_opengl_context(major, minor) {
+        return Some(if available {
+            report(
+                Backend::Eframe,
+                BackendSelectionReason::Default,
+                format!("opengl {major}.{minor} is available according to the platform detector"),
+            )
+        } else {
+            report(
+                Backend::SoftwareBackend,
+                BackendSelectionReason::GlProbeFailed,
+                format!(
+                    "opengl {major}.{minor} is not available according to the platform detector"
+                ),
+            )
+        });
+    }
+
+    let probe = || {
+        if options.isolated_probe {
+            probe_opengl_isolated(
+                options.allow_software_gl,
+                options.minimum_opengl_version,
+                options.probe_timeout,
+            )
+        } else {
+            probe_opengl_with_watchdog(
+                options.allow_software_gl,
+                options.minimum_opengl_version,
+                options.probe_timeout,
+            )
+        }
+    };
+
+    let Some(cache_dir) = options.probe_cache_path.as_deref() else {
+        return Some(probe());
+    };
+
+    let Some(fingerprint) = crate::probe_cache::driver_fingerprint() else {
+        //Without a fingerprint we cannot tell if the cached result is still valid.
+        return Some(probe());
+    };
+
+    //The result is only valid for the version it was probed with.
+    let fingerprint = if options.minimum_opengl_version == DEFAULT_MINIMUM_OPENGL_VERSION {
+        fingerprint
+    } else {
+        format!("{fingerprint};opengl={major}.{minor}")
+    };
+
+    if !options.force_reprobe
+        && let Some(backend) = crate::probe_cache::read(cache_dir, &fingerprint)
+    {
+        let reason = match backend {
+            Backend::SoftwareBackend => BackendSelectionReason::GlProbeFailed,
+            _ => BackendSelectionReason::Default,
+        };
+
+        return Some(report(backend, reason, "cached opengl probe result"));
+    }
+
+    let report = probe();
+    if matches!(
+        report.reason,
+        BackendSelectionReason::GlProbeCrashed | BackendSelectionReason::GlProbeTimedOut
+    ) {
+        //A crash or a hang might have been caused by something unrelated, so we probe again next time.
+        return Some(report);
+    }
+
+    crate::probe_cache::write(cache_dir, &fingerprint, report.backend);
+    Some(report)
+}
+
+/// Display adapters that are known to not work with eframe.
+/// Each pattern is matched against the name and the device id of the primary display adapter, ignoring case.
+/// The device id contains the pci vendor id, which catches renamed drivers.
+#[cfg(windows)]
+const PROBLEMATIC_DISPLAY_ADAPTERS: &[(&str, BackendSelectionReason)] = &[
+    //Process will segfault if we try eframe. This is the VMWare 3d driver. It's not good enough.
+    ("VEN_15AD", BackendSelectionReason::VmwareDriverPresent),
+    (
+        "VMware SVGA 3D",
+        BackendSelectionReason::VmwareDriverPresent,
+    ),
+    //Eframe will fail to launch due to missing gl extensions. This is the Virtualbox opengl driver.
+    ("VEN_80EE", BackendSelectionReason::VirtualBoxDriverPresent),
+    (
+        "VirtualBox Graphics Adapter",
+        BackendSelectionReason::VirtualBoxDriverPresent,
+    ),
+    //No driver is installed for the gpu, there is no opengl 3.2 without one.
+    (
+        "Microsoft Basic Display Adapter",
+        BackendSelectionReason::BasicDisplayAdapter,
+    ),
+    //These old intel gpus have unreliable opengl drivers.
+    ("Intel(R) G41 Express", BackendSelectionReason::OldIntelGpu),
+    (
+        "Intel(R) Q45/Q43 Express",
+        BackendSelectionReason::OldIntelGpu,
+    ),
+    (
+        "Mobile Intel(R) 4 Series Express",
+        BackendSelectionReason::OldIntelGpu,
+    ),
+];
+
+/// Checks the primary display adapter against the known problematic display adapters.
+/// If no adapter is marked as primary, then the first one is checked.
+#[cfg(windows)]
+fn classify_display_adapters(
+    adapters: &[crate::probe_cache::DisplayAdapter],
+) -> Option<BackendSelectionReport> {
+    let adapter = adapters
+        .iter()
+        .find(|adapter| adapter.primary)
+        .or_else(|| adapters.first())?;
+
+    let name = adapter.name.to_lowercase();
+    let device_id = adapter.device_id.to_lowercase();
+
+    PROBLEMATIC_DISPLAY_ADAPTERS
+        .iter()
+        .find(|(pattern, _)| {
+            let pattern = pattern.to_lowercase();
+            name.contains(&pattern) || device_id.contains(&pattern)
+        })
+        .map(|(_, reason)| {
+            report(
+                Backend::SoftwareBackend,
+                *reason,
+                format!("{} ({})", adapter.name, adapter.device_id),
+            )
+        })
+}
+
+/// Spawns the current executable to perform the opengl probe in a child process.
+/// A crashed child process is interpreted as opengl being unusable.
+/// The child process is killed if it does not exit within the timeout.
+#[cfg(windows)]
+fn probe_opengl_isolated(
+    allow_software_gl: bool,
+    (major, minor): (u8, u8),
+    timeout: Duration,
+) -> BackendSelectionReport {
+    let child = std::env::current_exe().and_then(|exe| {
+        std::process::Command::new(exe)
+            .args(std::env::args_os().skip(1))
+            .env(
+                PROBE_ENV_VAR,
+                if allow_software_gl {
+                    PROBE_ALLOW_SOFTWARE_GL
+                } else {
+                    "1"
+                },
+            )
+            .env(PROBE_OPENGL_VERSION_ENV_VAR, format!("{major}.{minor}"))
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+    });
+
+    let status = child.and_then(|mut child| {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(Some(status));
+            }
+
+            if std::time::Instant::now() >= deadline {
+                _ = child.kill();
+                _ = child.wait();
+                return Ok(None);
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    match status.map(|status| status.map(|status| status.code())) {
+        Ok(None) => {
+            log::warn!(
+                "The opengl probe process did not exit within {timeout:?}, using the software backend."
+            );
+            report(
+                Backend::SoftwareBackend,
+                BackendSelectionReason::GlProbeTimedOut,
+                format!("timeout {timeout:?}"),
+            )
+        }
+        Ok(Some(Some(PROBE_EXIT_CODE_EFRAME))) => report(
+            Backend::Eframe,
+            BackendSelectionReason::Default,
+            format!("opengl {major}.{minor} is available according to the probe process"),
+        ),
+        Ok(Some(Some(PROBE_EXIT_CODE_SOFTWARE_BACKEND))) => report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::GlProbeFailed,
+            format!("opengl {major}.{minor} is not available according to the probe process"),
+        ),
+        Ok(Some(Some(PROBE_EXIT_CODE_SOFTWARE_GL))) => report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::SoftwareGlRenderer,
+            "opengl is provided by a software renderer according to the probe process",
+        ),
+        Ok(Some(code)) => {
+            log::warn!(
+                "The opengl probe process crashed or exited unexpectedly with code={code:?}, using the software backend."
+            );
+            report(
+                Backend::SoftwareBackend,
+                BackendSelectionReason::GlProbeCrashed,
+                format!("exit code {code:?}"),
+            )
+        }
+        Err(e) => {
+            log::warn!(
+                "Failed to spawn the opengl probe process, probing in this process instead. err={e}"
+            );
+            probe_opengl_with_watchdog(allow_software_gl, (major, minor), timeout)
+        }
+    }
+}
+
+/// Performs the opengl probe in this process, guarded by a watchdog thread.
+///
+/// A hung graphics driver cannot be aborted, so if the probe does not finish within the timeout,
+/// then the watchdog relaunches the process with the software backend
+/// and exits this process with the exit code of the relaunched process once it exits.
+#[cfg(windows)]
+fn probe_opengl_with_watchdog(
+    allow_software_gl: bool,
+    minimum_version: (u8, u8),
+    timeout: Duration,
+) -> BackendSelectionReport {
+    let (done, finished) = std::sync::mpsc::channel::<()>();
+    let watchdog = std::thread::spawn(move || {
+        if finished.recv_timeout(timeout) != Err(std::sync::mpsc::RecvTimeoutError::Timeout) {
+            //The probe finished in time.
+            return;
+        }
+
+        if std::env::var_os(FALLBACK_ENV_VAR).is_some() {
+            //This process already is the relaunched process, relaunching again would never end.
+            log::error!("The opengl probe did not finish within {timeout:?}, exiting.");
+            std::process::exit(1);
+        }
+
+        log::error!(
+            "The opengl probe did not finish within {timeout:?}, relaunching with the software backend."
+        );
+
+        let status = std::env::current_exe().and_then(|exe| {
+            std::process::Command::new(exe)
+                .args(std::env::args_os().skip(1))
+                .env(FALLBACK_ENV_VAR, "1")
+                .status()
+        });
+
+        match status {
+            Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+            Err(e) => {
+                log::error!("Failed to relaunch with the software backend. err={e}");
+                std::process::exit(1);
+            }
+        }
+    });
+
+    let report = probe_opengl(allow_software_gl, minimum_version);
+    drop(done);
+    _ = watchdog.join();
+    report
+}
+
+/// Creates a small hidden opengl window to check if the opengl version on this system is sufficient for eframe.
+#[cfg(windows)]
+pub fn probe_opengl(allow_software_gl: bool, (major, minor): (u8, u8)) -> BackendSelectionReport {
+    let Ok(mut glfw) = glfw::init::<()>(None) else {
+        //No opengl at all, this is some virgin post-installer windows with no drivers.
+        return report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::GlProbeFailed,
+            "glfw could not be initialized",
+        );
+    };
+
+    //The minimum version for eframe to work appears to be opengl 3.2, unless the application requires more.
+    glfw.window_hint(glfw::WindowHint::ContextVersion(
+        u32::from(major),
+        u32::from(minor),
+    ));
+    glfw.window_hint(glfw::WindowHint::Visible(false));
+
+    let Some((wnd, events)) = glfw.create_window(
+        128,
+        128,
+        "opengl version detector",
+        glfw::WindowMode::Windowed,
+    ) else {
+        //Opengl is too old. This is a catch-all for "other" hypervisors with insufficient opengl implementations.
+        return report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::GlProbeFailed,
+            format!("could not create an opengl {major}.{minor} context"),
+        );
+    };
+
+    drop(events);
+
+    let renderer = gl_renderer(wnd);
+    if !allow_software_gl
+        && let Some(renderer) = &renderer
+        && is_software_gl_renderer(renderer)
+    {
+        //eframe would work, but the software backend is faster than a software opengl renderer.
+        return report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::SoftwareGlRenderer,
+            format!("GL_RENDERER={renderer}"),
+        );
+    }
+
+    report(
+        Backend::Eframe,
+        BackendSelectionReason::Default,
+        renderer.map_or_else(
+            || format!("opengl {major}.{minor} is available"),
+            |renderer| format!("opengl {major}.{minor} is available, GL_RENDERER={renderer}"),
+        ),
+    )
+}
+
+/// Returns true if the `GL_RENDERER` string belongs to a renderer that does not use the gpu.
+#[cfg(windows)]
+fn is_software_gl_renderer(renderer: &str) -> bool {
+    let renderer = renderer.to_lowercase();
+    ["llvmpipe", "softpipe", "swiftshader"]
+        .iter()
+        .any(|name| renderer.contains(name))
+}
+
+/// Queries the `GL_RENDERER` and `GL_VENDOR` strings of the context of the window.
+#[cfg(windows)]
+fn gl_renderer(mut wnd: glfw::PWindow) -> Option<String> {
+    use glfw::Context;
+
+    /// `GL_VENDOR` from the opengl headers.
+    const GL_VENDOR: u32 = 0x1F00;
+
+    /// `GL_RENDERER` from the opengl headers.
+    const GL_RENDERER: u32 = 0x1F01;
+
+    wnd.make_current();
+    let gl_get_string = wnd.get_proc_address("glGetString");
+    if gl_get_string.is_null() {
+        glfw::make_context_current(None);
+        return None;
+    }
+
+    let gl_get_string = unsafe {
+        std::mem::transmute::<glfw::GLProc, extern "system" fn(u32) -> *const std::ffi::c_char>(
+            gl_get_string,
+        )
+    };
+
+    let read = |name: u32| {
+        let value = gl_get_string(name);
+        if value.is_null() {
+            return None;
+        }
+
+        Some(
+            unsafe { std::ffi::CStr::from_ptr(value) }
+                .to_string_lossy()
+                .into_owned(),
+        )
+    };
+
+    let renderer = read(GL_RENDERER);
+    let vendor = read(GL_VENDOR);
+    glfw::make_context_current(None);
+    drop(wnd);
+
+    match (renderer, vendor) {
+        (Some(renderer), Some(vendor)) => Some(format!("{renderer} ({vendor})")),
+        (renderer, _) => renderer,
+    }
+}
+
+/// Tests of the linux detection with a fake platform detector, they do not depend on the system they run on.
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::platform_detector::FakePlatformDetector;
+
+    /// Runs the built-in detection with the detector.
+    fn detect(detector: FakePlatformDetector) -> BackendSelectionReport {
+        determine_backend(&DetectionOptions {
+            platform_detector: Arc::new(detector),
+            ..DetectionOptions::default()
+        })
+        .expect("the linux detection runs on every thread")
+    }
+
+    /// A local desktop with a gpu uses eframe.
+    #[test]
+    fn local_desktop_selects_eframe() {
+        let report = detect(FakePlatformDetector::linux_desktop());
+        assert_eq!(report.backend, Backend::Eframe);
+        assert_eq!(report.reason, BackendSelectionReason::Default);
+        assert_eq!(report.detail, "DISPLAY=:0");
+    }
+
+    /// Without a render node there is no gpu, so opengl would be llvmpipe at best.
+    #[test]
+    fn missing_render_node_selects_software_backend() {
+        let report = detect(FakePlatformDetector::linux_desktop().with_dir("/dev/dri", &["card0"]));
+        assert_eq!(report.backend, Backend::SoftwareBackend);
+        assert_eq!(report.reason, BackendSelectionReason::NoGpuRenderNode);
+    }
+
+    /// eframe cannot load opengl without libEGL or libGL.
+    #[test]
+    fn missing_gl_library_selects_software_backend() {
+        let report = detect(
+            FakePlatformDetector::default()
+                .with_env("DISPLAY", ":0")
+                .with_dir("/dev/dri", &["renderD128"]),
+        );
+        assert_eq!(report.backend, Backend::SoftwareBackend);
+        assert_eq!(report.reason, BackendSelectionReason::GlLibraryMissing);
+    }
+
+    /// The remote session checks run before the gpu checks.
+    #[test]
+    fn remote_session_selects_software_backend_despite_gpu() {
+        let report = detect(FakePlatformDetector::linux_desktop().with_env("XRDP_SESSION", "1"));
+        assert_eq!(report.backend, Backend::SoftwareBackend);
+        assert_eq!(report.reason, BackendSelectionReason::XrdpSession);
+    }
+
+    /// `WSLg` is detected before the gpu checks, it has no render node but a working gpu.
+    #[test]
+    fn wslg_selects_eframe_without_render_node() {
+        let report = detect(
+            FakePlatformDetector::default()
+                .with_file(
+                    "/proc/version",
+                    "Linux version 5.15.153.1-microsoft-standard-WSL2",
+                )
+                .with_dir("/mnt/wslg", &[])
+                .with_env("WAYLAND_DISPLAY", "wayland-0"),
+        );
+        assert_eq!(report.backend, Backend::Eframe);
+        assert_eq!(report.reason, BackendSelectionReason::Wslg);
+    }
+
+    /// A wayland session forwarded with waypipe uses the software backend.
+    #[test]
+    fn waypipe_selects_software_backend() {
+        let report = detect(
+            FakePlatformDetector::linux_desktop()
+                .without_env("DISPLAY")
+                .with_env("WAYLAND_DISPLAY", "wayland-0")
+                .with_file("/proc/self/stat", "100 (app) S 42 100 100")
+                .with_file("/proc/42/comm", "waypipe\n"),
+        );
+        assert_eq!(report.backend, Backend::SoftwareBackend);
+        assert_eq!(report.reason, BackendSelectionReason::WaypipeSession);
+    }
+
+    /// Environment variables of a test case.
+    type EnvVars = &'static [(&'static str, &'static str)];
+
+    /// Every remote session is detected from its environment variables, local displays are not.
+    #[test]
+    fn remote_session_classification() {
+        let cases: &[(EnvVars, Option<BackendSelectionReason>)] = &[
+            (&[("DISPLAY", ":0")], None),
+            (&[("DISPLAY", "/tmp/launch-abc/unix:0")], None),
+            (&[], None),
+            (
+                &[("DISPLAY", "remote-host:0")],
+                Some(BackendSelectionReason::RemoteX11Display),
+            ),
+            (
+                &[
+                    ("DISPLAY", "localhost:10.0"),
+                    ("SSH_CONNECTION", "10.0.0.2 50000 10.0.0.1 22"),
+                ],
+                Some(BackendSelectionReason::SshForwarding),
+            ),
+            (
+                &[
+                    ("DISPLAY", "localhost:10.0"),
+                    ("SSH_CLIENT", "10.0.0.2 50000 22"),
+                ],
+                Some(BackendSelectionReason::SshForwarding),
+            ),
+            //ssh without a forwarded display is a local session, e.g. when the app runs in a nested session.
+            (
+                &[
+                    ("DISPLAY", ":0"),
+                    ("SSH_CONNECTION", "10.0.0.2 50000 10.0.0.1 22"),
+                ],
+                None,
+            ),
+            (
+                &[("DISPLAY", ":10"), ("XRDP_SESSION", "1")],
+                Some(BackendSelectionReason::XrdpSession),
+            ),
+            (
+                &[("DISPLAY", ":50"), ("X2GO_SESSION", "user-50-123")],
+                Some(BackendSelectionReason::X2goSession),
+            ),
+            //x2go sets the NX variables as well.
+            (
+                &[("X2GO_SESSION", "user-50-123"), ("NX_CLIENT", "x2go")],
+                Some(BackendSelectionReason::X2goSession),
+            ),
+            (
+                &[("DISPLAY", ":1001"), ("NX_CLIENT", "/usr/NX/bin/nxclient")],
+                Some(BackendSelectionReason::NoMachineSession),
+            ),
+            (
+                &[("DISPLAY", ":1"), ("VNCDESKTOP", "host:1 (user)")],
+                Some(BackendSelectionReason::VncSession),
+            ),
+            (
+                &[("DISPLAY", ":20"), ("CHROME_REMOTE_DESKTOP_SESSION", "1")],
+                Some(BackendSelectionReason::ChromeRemoteDesktopSession),
+            ),
+        ];
+
+        for (env, expected) in cases {
+            let detector = env
+                .iter()
+                .fold(FakePlatformDetector::default(), |detector, (key, value)| {
+                    detector.with_env(key, value)
+                });
+            let report = detect_remote_session(&detector);
+            assert_eq!(
+                report.as_ref().map(|report| report.reason),
+                *expected,
+                "{env:?}"
+            );
+            if let Some(report) = report {
+                assert_eq!(report.backend, Backend::SoftwareBackend, "{env:?}");
+            }
+        }
+    }
+
+    /// WSL1, WSL2 with and without `WSLg` are told apart by the kernel version and the `WSLg` mount.
+    #[test]
+    fn wsl_classification() {
+        let wsl2 = "Linux version 5.15.153.1-microsoft-standard-WSL2";
+        let cases: &[(
+            FakePlatformDetector,
+            Option<(Backend, BackendSelectionReason)>,
+        )] = &[
+            (
+                FakePlatformDetector::default()
+                    .with_file("/proc/version", "Linux version 6.8.0-45-generic"),
+                None,
+            ),
+            (FakePlatformDetector::default(), None),
+            (
+                FakePlatformDetector::default()
+                    .with_file("/proc/version", "Linux version 4.4.0-19041-Microsoft"),
+                Some((Backend::SoftwareBackend, BackendSelectionReason::Wsl1)),
+            ),
+            (
+                FakePlatformDetector::default()
+                    .with_file("/proc/version", wsl2)
+                    .with_dir("/mnt/wslg", &[])
+                    .with_env("WAYLAND_DISPLAY", "wayland-0"),
+                Some((Backend::Eframe, BackendSelectionReason::Wslg)),
+            ),
+            (
+                FakePlatformDetector::default()
+                    .with_file("/proc/version", wsl2)
+                    .with_env("DISPLAY", "172.20.0.1:0"),
+                Some((
+                    Backend::SoftwareBackend,
+                    BackendSelectionReason::WslWithoutWslg,
+                )),
+            ),
+            //WSLg is installed, but WAYLAND_DISPLAY was unset, e.g. to force an X server on windows.
+            (
+                FakePlatformDetector::default()
+                    .with_file("/proc/version", wsl2)
+                    .with_dir("/mnt/wslg", &[]),
+                Some((
+                    Backend::SoftwareBackend,
+                    BackendSelectionReason::WslWithoutWslg,
+                )),
+            ),
+            //A custom WSL2 kernel whose version does not mention microsoft.
+            (
+                FakePlatformDetector::default()
+                    .with_file("/proc/version", "Linux version 6.6.36-custom-WSL2")
+                    .with_file("/proc/sys/fs/binfmt_misc/WSLInterop", "enabled"),
+                Some((
+                    Backend::SoftwareBackend,
+                    BackendSelectionReason::WslWithoutWslg,
+                )),
+            ),
+        ];
+
+        for (detector, expected) in cases {
+            let report = detect_wsl(detector);
+            assert_eq!(
+                report.map(|report| (report.backend, report.reason)),
+                *expected,
+                "{detector:?}"
+            );
+        }
+    }
+
+    /// waypipe is detected from its environment variables or a waypipe process among the ancestors.
+    #[test]
+    fn waypipe_classification() {
+        let wayland = FakePlatformDetector::default().with_env("WAYLAND_DISPLAY", "wayland-0");
+        let cases: &[(FakePlatformDetector, bool)] = &[
+            (FakePlatformDetector::default(), false),
+            //Without wayland waypipe cannot be involved, even if a variable is left over.
+            (
+                FakePlatformDetector::default().with_env("WAYPIPE_SOCKET", "/tmp/waypipe"),
+                false,
+            ),
+            (wayland.clone(), false),
+            (
+                wayland.clone().with_env("WAYPIPE_SOCKET", "/tmp/waypipe"),
+                true,
+            ),
+            (
+                FakePlatformDetector::default()
+                    .with_env("WAYLAND_SOCKET", "3")
+                    .with_env("WAYPIPE_DISPLAY", "wayland-1"),
+                true,
+            ),
+            (
+                wayland
+                    .clone()
+                    .with_file("/proc/self/stat", "100 (my app) S 42 100 100")
+                    .with_file("/proc/42/stat", "42 (waypipe) S 7 42 42")
+                    .with_file("/proc/42/comm", "waypipe\n"),
+                true,
+            ),
+            //waypipe is the grandparent, the app was started by a shell.
+            (
+                wayland
+                    .clone()
+                    .with_file("/proc/self/stat", "100 (app) S 42 100 100")
+                    .with_file("/proc/42/comm", "bash\n")
+                    .with_file("/proc/42/stat", "42 (bash) S 7 42 42")
+                    .with_file("/proc/7/comm", "waypipe\n"),
+                true,
+            ),
+            //The ancestors end at init without a waypipe process.
+            (
+                wayland
+                    .clone()
+                    .with_file("/proc/self/stat", "100 (app) S 42 100 100")
+                    .with_file("/proc/42/comm", "gnome-shell\n")
+                    .with_file("/proc/42/stat", "42 (gnome-shell) S 1 42 42"),
+                false,
+            ),
+            //A process name with spaces and parentheses does not confuse the parsing.
+            (
+                wayland
+                    .with_file("/proc/self/stat", "100 (a (b) c) S 42 100 100")
+                    .with_file("/proc/42/comm", "waypipe\n"),
+                true,
+            ),
+        ];
+
+        for (detector, expected) in cases {
+            let report = detect_waypipe(detector);
+            assert_eq!(report.is_some(), *expected, "{detector:?}");
+            if let Some(report) = report {
+                assert_eq!(report.reason, BackendSelectionReason::WaypipeSession);
+            }
+        }
+    }
+
+    /// The configuration runs the detection with its detector.
+    #[test]
+    fn configuration_probes_with_its_detector() {
+        let report = crate::BackendConfiguration::default()
+            .with_platform_detector(Box::new(
+                FakePlatformDetector::linux_desktop().with_env("NX_CLIENT", "/usr/NX/bin/nxclient"),
+            ))
+            .probe_backend()
+            .expect("the linux detection runs on every thread");
+        assert_eq!(report.backend, Backend::SoftwareBackend);
+        assert_eq!(report.reason, BackendSelectionReason::NoMachineSession);
+    }
+}
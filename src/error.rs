@@ -0,0 +1,177 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Errors that can be returned when running an app.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BackendSelectorError {
+    /// `run_app` was not called in the main thread.
+    NotMainThread,
+
+    /// `run_app` was called while the application was running.
+    AlreadyLaunched,
+
+    /// Another instance of the app is running, see `BackendConfiguration::with_single_instance`.
+    AlreadyRunning,
+
+    /// `run_app` was called again after the event loop exited, but the backend failed.
+    /// Most likely the backend could not create another event loop.
+    /// This is always the case for the software backend, it creates a new event loop on every run.
+    RelaunchUnsupported(Box<Self>),
+
+    /// The app factory returned an error.
+    AppCreation(Box<dyn Error + Send + Sync>),
+
+    /// eframe failed.
+    #[cfg(not(target_arch = "wasm32"))]
+    Eframe(eframe::Error),
+
+    /// The software backend failed.
+    SoftwareBackend(Box<dyn Error>),
+
+    /// The launch function of a custom backend failed.
+    CustomBackend(Box<dyn Error>),
+
+    /// eframe failed to initialize and the process relaunched with the software backend did not exit successfully.
+    FallbackFailed(std::process::ExitStatus),
+
+    /// `BackendConfiguration::with_allowed_backends` does not allow any backend.
+    NoAllowedBackend,
+
+    /// The backend cannot be launched by this build on this platform.
+    BackendUnavailable(crate::Backend),
+
+    /// The scale factor is not in the range (0, 10].
+    InvalidScaleFactor(f32),
+
+    /// The canvas element the app should be drawn on does not exist.
+    #[cfg(target_arch = "wasm32")]
+    CanvasNotFound(String),
+}
+
+/// Error returned when the backend could not be overwritten.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum OverwriteError {
+    /// The application is running, the backend can no longer be changed.
+    AlreadyLaunched,
+
+    /// The backend cannot be launched by this build on this platform.
+    Unavailable(crate::Backend),
+}
+
+impl Display for OverwriteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AlreadyLaunched => f.write_str("Application already launched"),
+            Self::Unavailable(backend) => write!(f, "Backend {backend} is not available"),
+        }
+    }
+}
+
+impl Error for OverwriteError {}
+
+impl From<OverwriteError> for BackendSelectorError {
+    fn from(value: OverwriteError) -> Self {
+        match value {
+            OverwriteError::AlreadyLaunched => Self::AlreadyLaunched,
+            OverwriteError::Unavailable(backend) => Self::BackendUnavailable(backend),
+        }
+    }
+}
+
+/// Error returned when the icon data is invalid.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum IconError {
+    /// The width or height is 0 or the icon is too large.
+    InvalidDimensions { width: u32, height: u32 },
+
+    /// The length of the rgba buffer does not match width * height * 4.
+    WrongBufferLength { expected: usize, actual: usize },
+}
+
+impl Display for IconError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidDimensions { width, height } => {
+                write!(f, "Invalid icon dimensions {width}x{height}")
+            }
+            Self::WrongBufferLength { expected, actual } => write!(
+                f,
+                "Icon buffer has {actual} bytes, expected {expected} bytes"
+            ),
+        }
+    }
+}
+
+impl Error for IconError {}
+
+/// Error returned when parsing the name of a backend fails.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseBackendError(pub String);
+
+impl Display for ParseBackendError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown backend {}, expected one of: software", self.0)?;
+        for backend in crate::Backend::ALL {
+            write!(f, ", {backend}")?;
+        }
+
+        f.write_str(", custom-<id>")
+    }
+}
+
+impl Error for ParseBackendError {}
+
+impl Display for BackendSelectorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotMainThread => f.write_str("Current thread is not the main thread"),
+            Self::AlreadyLaunched => f.write_str("Application already launched"),
+            Self::AlreadyRunning => f.write_str("Another instance of the application is running"),
+            Self::RelaunchUnsupported(e) => {
+                write!(f, "Relaunching the application is not supported: {e}")
+            }
+            Self::AppCreation(e) => write!(f, "App creation failed: {e}"),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Eframe(e) => write!(f, "eframe failed: {e}"),
+            Self::SoftwareBackend(e) => write!(f, "Software backend failed: {e}"),
+            Self::CustomBackend(e) => write!(f, "Custom backend failed: {e}"),
+            Self::FallbackFailed(status) => {
+                write!(
+                    f,
+                    "Process relaunched with the software backend failed: {status}"
+                )
+            }
+            Self::NoAllowedBackend => f.write_str("No backend is allowed"),
+            Self::BackendUnavailable(backend) => write!(f, "Backend {backend} is not available"),
+            Self::InvalidScaleFactor(scale_factor) => {
+                write!(f, "Invalid scale factor {scale_factor}")
+            }
+            #[cfg(target_arch = "wasm32")]
+            Self::CanvasNotFound(id) => write!(f, "Canvas with the id {id} not found"),
+        }
+    }
+}
+
+impl Error for BackendSelectorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::AppCreation(e) => Some(e.as_ref()),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Eframe(e) => Some(e),
+            Self::SoftwareBackend(e) | Self::CustomBackend(e) => Some(e.as_ref()),
+            Self::RelaunchUnsupported(e) => Some(e.as_ref()),
+            Self::NotMainThread
+            | Self::AlreadyLaunched
+            | Self::AlreadyRunning
+            | Self::FallbackFailed(_)
+            | Self::NoAllowedBackend
+            | Self::BackendUnavailable(_)
+            | Self::InvalidScaleFactor(_) => None,
+            #[cfg(target_arch = "wasm32")]
+            Self::CanvasNotFound(_) => None,
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+/// Number of frames the rolling statistics are computed over.
+const FRAME_STATS_WINDOW: usize = 120;
+
+/// Timing statistics of the recent frames, see `BackendInterop::frame_stats`.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[non_exhaustive]
+pub struct FrameStats {
+    /// Number of frames since the app was started.
+    pub frame_count: u64,
+
+    /// How long the last frame took on the cpu. None if the backend does not measure it.
+    pub last_frame_time: Option<Duration>,
+
+    /// Average cpu time of the recent frames. None if the backend does not measure it.
+    pub average_frame_time: Option<Duration>,
+
+    /// 95th percentile of the cpu time of the recent frames. None if the backend does not measure it.
+    pub percentile_95_frame_time: Option<Duration>,
+
+    /// Frames per second, measured from the time between the recent frames. None before the second frame.
+    pub fps: Option<f32>,
+}
+
+/// Ring buffer of the timings of the recent frames.
+#[derive(Debug, Clone)]
+pub struct FrameTimings {
+    /// Cpu time of the recent frames in seconds.
+    cpu_times: [f32; FRAME_STATS_WINDOW],
+
+    /// Number of valid entries in `cpu_times`.
+    cpu_time_count: usize,
+
+    /// Seconds between the starts of the recent frames.
+    intervals: [f32; FRAME_STATS_WINDOW],
+
+    /// Number of valid entries in `intervals`.
+    interval_count: usize,
+
+    /// Number of frames recorded so far.
+    frame_count: u64,
+
+    /// Cpu time of the last frame in seconds.
+    last_cpu_time: Option<f32>,
+
+    /// Input time of egui at the start of the previous frame.
+    last_frame_start: Option<f64>,
+}
+
+impl Default for FrameTimings {
+    fn default() -> Self {
+        Self {
+            cpu_times: [0.0; FRAME_STATS_WINDOW],
+            cpu_time_count: 0,
+            intervals: [0.0; FRAME_STATS_WINDOW],
+            interval_count: 0,
+            frame_count: 0,
+            last_cpu_time: None,
+            last_frame_start: None,
+        }
+    }
+}
+
+impl FrameTimings {
+    /// Records the start of a frame.
+    /// `cpu_time` is the cpu time of the previous frame in seconds as reported by the backend,
+    /// `time` is the input time of egui in seconds.
+    pub fn record(&mut self, cpu_time: Option<f32>, time: f64) {
+        //The ring buffer index is the frame count modulo the window, it always fits into usize.
+        #[allow(clippy::cast_possible_truncation)]
+        let index = (self.frame_count % FRAME_STATS_WINDOW as u64) as usize;
+        self.frame_count += 1;
+
+        self.last_cpu_time = cpu_time.filter(|cpu_time| cpu_time.is_finite() && *cpu_time >= 0.0);
+        if let Some(cpu_time) = self.last_cpu_time {
+            self.cpu_times[index] = cpu_time;
+            self.cpu_time_count = (self.cpu_time_count + 1).min(FRAME_STATS_WINDOW);
+        }
+
+        if let Some(last_frame_start) = self.last_frame_start.replace(time) {
+            //The precision of the interval is not important, it is only shown to the user.
+            #[allow(clippy::cast_possible_truncation)]
+            let interval = (time - last_frame_start).max(0.0) as f32;
+            self.intervals[index] = interval;
+            self.interval_count = (self.interval_count + 1).min(FRAME_STATS_WINDOW);
+        }
+    }
+
+    /// Computes the statistics of the recorded frames.
+    pub fn stats(&self) -> FrameStats {
+        let to_duration = |secs: f32| Duration::try_from_secs_f32(secs).ok();
+
+        let mut cpu_times = self.cpu_times;
+        let cpu_times = &mut cpu_times[..self.cpu_time_count];
+        cpu_times.sort_unstable_by(f32::total_cmp);
+
+        let average_frame_time = average(cpu_times).and_then(to_duration);
+
+        //The index is at most the number of frames in the window.
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let percentile_95_frame_time = (!cpu_times.is_empty())
+            .then(|| cpu_times[((cpu_times.len() - 1) as f32 * 0.95).round() as usize])
+            .and_then(to_duration);
+
+        let fps = average(&self.intervals[..self.interval_count])
+            .filter(|interval| *interval > 0.0)
+            .map(|interval| 1.0 / interval);
+
+        FrameStats {
+            frame_count: self.frame_count,
+            last_frame_time: self.last_cpu_time.and_then(to_duration),
+            average_frame_time,
+            percentile_95_frame_time,
+            fps,
+        }
+    }
+}
+
+/// Returns the average of the values. None if there are none.
+//The window is small, the count is exact as f32.
+#[allow(clippy::cast_precision_loss)]
+fn average(values: &[f32]) -> Option<f32> {
+    (!values.is_empty()).then(|| values.iter().sum::<f32>() / values.len() as f32)
+}
+
+/// Shows the statistics as rows of a two column grid, used by `BackendInterop::show_debug_ui`.
+pub fn show_frame_stats(ui: &mut egui::Ui, stats: &FrameStats) {
+    let millis = |frame_time: Option<Duration>| {
+        frame_time.map_or_else(
+            || String::from("-"),
+            |frame_time| format!("{:.2} ms", frame_time.as_secs_f64() * 1000.0),
+        )
+    };
+
+    let rows = [
+        ("Frames", stats.frame_count.to_string()),
+        ("Last frame time", millis(stats.last_frame_time)),
+        ("Average frame time", millis(stats.average_frame_time)),
+        (
+            "95th percentile frame time",
+            millis(stats.percentile_95_frame_time),
+        ),
+        (
+            "FPS",
+            stats
+                .fps
+                .map_or_else(|| String::from("-"), |fps| format!("{fps:.1}")),
+        ),
+    ];
+
+    for (name, value) in rows {
+        ui.label(name);
+        ui.label(value);
+        ui.end_row();
+    }
+}
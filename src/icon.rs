@@ -0,0 +1,25 @@
+use crate::IconError;
+use egui::IconData;
+
+/// Creates the icon data after validating the dimensions and the buffer length.
+pub fn icon_data(width: u32, height: u32, rgba: Vec<u8>) -> Result<IconData, IconError> {
+    if width == 0 || height == 0 {
+        return Err(IconError::InvalidDimensions { width, height });
+    }
+
+    let expected = usize::try_from(u64::from(width) * u64::from(height) * 4)
+        .map_err(|_| IconError::InvalidDimensions { width, height })?;
+
+    if rgba.len() != expected {
+        return Err(IconError::WrongBufferLength {
+            expected,
+            actual: rgba.len(),
+        });
+    }
+
+    Ok(IconData {
+        rgba,
+        width,
+        height,
+    })
+}
@@ -1,15 +1,42 @@
 #[cfg(all(not(feature = "glow"), not(feature = "wgpu")))]
 compile_error!("Either glow or wgpu feature must be enabled for eframe to be useful.");
 
+use crate::detection::{
+    DetectionOptions, FALLBACK_ENV_VAR, determine_backend, handle_probe_subprocess,
+    run_custom_probes,
+};
+use crate::frame_stats::{FrameTimings, show_frame_stats};
+use crate::icon::icon_data;
+use crate::monitor::current_monitor;
+use crate::report::{report, set_selection_report};
+#[cfg(feature = "single-instance")]
+use crate::single_instance::InstanceLockState;
+use crate::software_screenshot::ScreenshotPlugin;
+#[cfg(feature = "persistence")]
+use crate::storage::KVStorage;
+use crate::storage::run_migrations;
+use crate::system_theme::detect_system_theme;
+use crate::{
+    BackendCapabilities, BackendSelectionReason, BackendSelectionReport, BackendSelectorError,
+    FrameStats, IconError, MonitorInfo, OverwriteError, ParseBackendError, PlatformDetector,
+    StorageCompression, StorageExt, StorageFormat, StorageMigration, SystemTheme,
+    backend_from_args, selection_report,
+};
 use eframe::egui::Context;
 use eframe::{Frame, IntegrationInfo, NativeOptions, Storage};
 use egui::ViewportBuilder;
 use egui_software_backend::{SoftwareBackend, SoftwareBackendAppConfiguration};
 use main_thread::IsMainThread;
+use std::any::Any;
 use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::ops::{Deref, DerefMut};
-use std::sync::atomic::AtomicUsize;
-use std::sync::atomic::Ordering::Relaxed;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::Duration;
 
 /// Number of elements in the enum below
 const NUM_BACKENDS: usize = 2;
@@ -20,6 +47,74 @@ const NUM_BACKENDS: usize = 2;
 pub enum Backend {
     SoftwareBackend,
     Eframe,
+
+    /// A backend registered with `BackendConfiguration::register_custom_backend`, identified by its id.
+    CustomBackend(u32),
+}
+
+impl Backend {
+    /// All built-in backends in the default order of preference.
+    pub const ALL: [Self; NUM_BACKENDS] = [Self::Eframe, Self::SoftwareBackend];
+
+    /// Returns the name of the backend. Parsing the name with `FromStr` returns the backend again.
+    /// For custom backends this is "custom", the name returned by `Display` includes the id.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::SoftwareBackend => "software-backend",
+            Self::Eframe => "eframe",
+            Self::CustomBackend(_) => "custom",
+        }
+    }
+
+    /// Returns true if this build can launch the backend on this platform.
+    /// Custom backends are always reported as available,
+    /// `run_app` fails if they are not registered with its configuration.
+    #[must_use]
+    pub fn is_available(self) -> bool {
+        matches!(self, Self::CustomBackend(_)) || available_backends().contains(&self)
+    }
+}
+
+/// Returns the backends this build can launch on this platform, in the default order of preference.
+#[must_use]
+pub const fn available_backends() -> &'static [Backend] {
+    //Both backends are always compiled in, eframe requires the glow or wgpu feature which is checked at compile time.
+    &Backend::ALL
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CustomBackend(id) => write!(f, "{}-{id}", self.as_str()),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = ParseBackendError;
+
+    /// Parses the name of a backend, ignoring case.
+    /// Accepts the names returned by `as_str`, "software" for the software backend
+    /// and "custom-<id>" for custom backends.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("software") {
+            return Ok(Self::SoftwareBackend);
+        }
+
+        if let Some((prefix, id)) = s.split_once('-')
+            && prefix.eq_ignore_ascii_case(Self::CustomBackend(0).as_str())
+            && let Ok(id) = id.parse()
+        {
+            return Ok(Self::CustomBackend(id));
+        }
+
+        Self::ALL
+            .into_iter()
+            .find(|backend| s.eq_ignore_ascii_case(backend.as_str()))
+            .ok_or_else(|| ParseBackendError(s.to_string()))
+    }
 }
 
 //0 - not decided
@@ -27,32 +122,131 @@ pub enum Backend {
 //2 - Eframe not launched
 //3 - SoftwareBackend launched
 //4 - Eframe launched
+//5 + 2 * id - CustomBackend(id) not launched
+//6 + 2 * id - CustomBackend(id) launched
 
 /// Static state enum.
-static STATE: AtomicUsize = AtomicUsize::new(0);
+static STATE: AtomicU64 = AtomicU64::new(0);
 
-/// Overwrites the selected backend.
-/// This has no effect if the application was already launched.
-pub fn overwrite_backend(backend: Backend) {
-    let state = STATE.load(Relaxed);
-    if state > NUM_BACKENDS {
-        return;
+/// Returns the state of the backend once it is selected.
+const fn selected_state(backend: Backend) -> u64 {
+    match backend {
+        Backend::SoftwareBackend => 1,
+        Backend::Eframe => 2,
+        Backend::CustomBackend(id) => 5 + 2 * id as u64,
     }
+}
 
+/// Returns the state of the backend once it is launched.
+const fn launched_state(backend: Backend) -> u64 {
     match backend {
-        Backend::SoftwareBackend => {
-            _ = STATE.compare_exchange(state, 1, Relaxed, Relaxed);
+        Backend::SoftwareBackend => 3,
+        Backend::Eframe => 4,
+        Backend::CustomBackend(id) => 6 + 2 * id as u64,
+    }
+}
+
+/// Returns the backend of the state, None if no backend was selected yet.
+const fn state_backend(state: u64) -> Option<Backend> {
+    match state {
+        0 => None,
+        1 | 3 => Some(Backend::SoftwareBackend),
+        2 | 4 => Some(Backend::Eframe),
+        #[allow(clippy::cast_possible_truncation)]
+        _ => Some(Backend::CustomBackend(((state - 5) / 2) as u32)),
+    }
+}
+
+/// Returns true if the state is the state of a launched backend.
+const fn is_launched_state(state: u64) -> bool {
+    match state_backend(state) {
+        Some(backend) => state == launched_state(backend),
+        None => false,
+    }
+}
+
+/// Time spent in the first run of the backend detection.
+static DETECTION_DURATION: OnceLock<Duration> = OnceLock::new();
+
+/// Static state, true once a backend ran its event loop and returned.
+/// Launch errors that happen before the backend is started do not set it.
+static EVENT_LOOP_EXITED: AtomicBool = AtomicBool::new(false);
+
+/// Shortest interval the state is saved in, so a tiny interval does not save every frame.
+const MIN_AUTO_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Name of the file inside the storage directory where eframe keeps its own state, like the window state.
+#[cfg(feature = "persistence")]
+const EFRAME_STATE_FILE_NAME: &str = "eframe.ron";
+
+/// Name of the file inside its default directory where eframe stores the state when it is used directly.
+#[cfg(feature = "persistence")]
+const LEGACY_EFRAME_FILE_NAME: &str = "app.ron";
+
+/// Storage key of the backend the user prefers. An empty value means there is no preference.
+const PREFERRED_BACKEND_KEY: &str = "__egui_backend_selector::preferred_backend";
+
+/// Overwrites the selected backend.
+/// Once this returned Ok, the backend is the one used by the next call to `run_app`,
+/// unless it is overwritten again.
+///
+/// # Errors
+/// * If the application is running, then the backend can no longer be changed.
+/// * If the backend is not available in this build, see `available_backends`.
+pub fn overwrite_backend(backend: Backend) -> Result<(), OverwriteError> {
+    overwrite_backend_with_report(report(
+        backend,
+        BackendSelectionReason::Overwritten,
+        String::new(),
+    ))
+}
+
+/// Overwrites the selected backend and records why.
+/// Fails if the application is running.
+fn overwrite_backend_with_report(report: BackendSelectionReport) -> Result<(), OverwriteError> {
+    if !report.backend.is_available() {
+        return Err(OverwriteError::Unavailable(report.backend));
+    }
+
+    let new_state = selected_state(report.backend);
+
+    let mut state = STATE.load(Acquire);
+    loop {
+        if is_launched_state(state) {
+            return Err(OverwriteError::AlreadyLaunched);
         }
-        Backend::Eframe => {
-            _ = STATE.compare_exchange(state, 2, Relaxed, Relaxed);
+
+        //A concurrent get_backend might have selected a backend in the meantime, so we retry until we win.
+        match STATE.compare_exchange_weak(state, new_state, AcqRel, Acquire) {
+            Ok(_) => {
+                set_selection_report(report);
+                return Ok(());
+            }
+            Err(actual) => state = actual,
         }
     }
 }
 
-/// Returns true if the application was already launched and the selected backend can no longer be changed
-/// by calling the `overwrite_backend` function.
+/// Returns true if the selected backend was chosen explicitly instead of detected,
+/// by calling `overwrite_backend`, on the command line, by a stored preference or by the eframe fallback.
+#[cfg(feature = "persistence")]
+fn is_explicit_selection() -> bool {
+    STATE.load(Acquire) != 0
+        && selection_report().is_some_and(|report| {
+            matches!(
+                report.reason,
+                BackendSelectionReason::Overwritten
+                    | BackendSelectionReason::CommandLine
+                    | BackendSelectionReason::StoredPreference
+                    | BackendSelectionReason::EframeFailed
+            )
+        })
+}
+
+/// Returns true if the application is running and the selected backend can no longer be changed
+/// by calling the `overwrite_backend` function. This returns false again once the event loop exited.
 pub fn is_launched() -> bool {
-    STATE.load(Relaxed) > NUM_BACKENDS
+    is_launched_state(STATE.load(Acquire))
 }
 
 /// The function returns the backend selected to be used for egui.
@@ -62,32 +256,168 @@ pub fn is_launched() -> bool {
 ///
 /// This function is guaranteed to never return None if it's called in the main thread.
 ///
+/// Probes registered with `register_probe` are run before the built-in detection.
+///
+#[must_use]
 pub fn get_backend() -> Option<Backend> {
-    let state = STATE.load(Relaxed);
-    Some(match state {
-        2 | 4 => Backend::Eframe,
-        3 | 1 => Backend::SoftwareBackend,
-        _ => {
-            return match determine_backend() {
-                None => None,
-                Some(Backend::SoftwareBackend) => {
-                    _ = STATE.compare_exchange(0, 1, Relaxed, Relaxed);
-                    Some(Backend::SoftwareBackend)
-                }
-                Some(Backend::Eframe) => {
-                    _ = STATE.compare_exchange(0, 2, Relaxed, Relaxed);
-                    Some(Backend::Eframe)
-                }
-            };
-        }
-    })
+    select_backend(&DetectionOptions::default())
+}
+
+/// Returns how long the backend detection took, including the probes registered with `register_probe`.
+///
+/// Returns None if the detection did not run yet, for example if the backend was set with `overwrite_backend`.
+/// The detection runs before the app is created, so this is available in the first `App::update` call.
+#[must_use]
+pub fn backend_detection_duration() -> Option<Duration> {
+    DETECTION_DURATION.get().copied()
+}
+
+/// Determines the backend like `get_backend` without selecting it.
+///
+/// The result can still be changed with `overwrite_backend`, and `run_app` runs the detection again.
+/// This ignores the backend set with `overwrite_backend`.
+/// # Returns
+/// This function may return None if called outside the main thread, like `get_backend`.
+#[must_use]
+pub fn probe_backend() -> Option<Backend> {
+    probe_backend_with_reason().map(|(backend, _)| backend)
+}
+
+/// Determines the backend and the reason for it like `get_backend` without selecting it.
+/// See `probe_backend`.
+#[must_use]
+pub fn probe_backend_with_reason() -> Option<(Backend, BackendSelectionReason)> {
+    let report = run_custom_probes().or_else(|| determine_backend(&DetectionOptions::default()))?;
+    Some((report.backend, report.reason))
+}
+
+/// Implementation of `get_backend` which allows the detection to be configured.
+fn select_backend(options: &DetectionOptions) -> Option<Backend> {
+    if let Some(backend) = state_backend(STATE.load(Acquire)) {
+        return Some(backend);
+    }
+
+    let start = std::time::Instant::now();
+    let report = run_custom_probes().or_else(|| determine_backend(options));
+    let duration = start.elapsed();
+    log::debug!("Backend detection took {duration:?}");
+    _ = DETECTION_DURATION.set(duration);
+
+    let report = report?;
+    let backend = report.backend;
+
+    Some(
+        match STATE.compare_exchange(0, selected_state(backend), AcqRel, Acquire) {
+            Ok(_) => {
+                set_selection_report(report);
+                backend
+            }
+            //The backend was overwritten while the detection ran, the overwrite wins.
+            Err(actual) => state_backend(actual).unwrap_or(backend),
+        },
+    )
 }
 
 /// Platform-specific interop to interact with the backend
 #[non_exhaustive]
 pub enum BackendInterop<'a> {
     SoftwareBackend(SoftwareBackendInterop<'a>),
-    Eframe(&'a mut Frame),
+    Eframe(EframeInterop<'a>),
+
+    /// Passed to the app by a backend registered with `BackendConfiguration::register_custom_backend`.
+    Custom(&'a mut CustomBackendInterop),
+}
+
+/// State of the interop that is kept between frames.
+#[derive(Default)]
+#[allow(clippy::struct_excessive_bools)]
+struct InteropState {
+    /// The context of the frame, returned by `egui_context`.
+    ctx: Context,
+
+    /// The app requested to exit by calling `request_exit`.
+    exit_requested: bool,
+
+    /// The cursor last set by calling `set_cursor`.
+    cursor: egui::CursorIcon,
+
+    /// The cursor was set during the current frame.
+    cursor_set: bool,
+
+    /// Is the window currently fullscreen? None if the backend does not report it.
+    fullscreen: Option<bool>,
+
+    /// The fullscreen state requested by calling `set_fullscreen` during the current frame.
+    requested_fullscreen: Option<bool>,
+
+    /// The window level requested by calling `set_always_on_top` during the current frame.
+    requested_window_level: Option<egui::WindowLevel>,
+
+    /// The decorations requested by calling `set_decorations` during the current frame.
+    requested_decorations: Option<bool>,
+
+    /// Is the window currently maximized? None if the backend does not report it.
+    maximized: Option<bool>,
+
+    /// Is the window currently minimized? None if the backend does not report it.
+    minimized: Option<bool>,
+
+    /// The maximized state requested during the current frame.
+    requested_maximized: Option<bool>,
+
+    /// The minimized state requested during the current frame.
+    requested_minimized: Option<bool>,
+
+    /// The scale factor set by calling `set_scale_factor` during the current frame.
+    requested_scale_factor: Option<f32>,
+
+    /// The zoom factor set by calling `set_zoom_factor` during the current frame.
+    requested_zoom_factor: Option<f32>,
+
+    /// The inner size set by calling `set_inner_size` during the current frame.
+    requested_inner_size: Option<egui::Vec2>,
+
+    /// The attention requested by calling `request_user_attention` during the current frame.
+    requested_user_attention: Option<egui::UserAttentionType>,
+
+    /// A window drag was started by calling `start_window_drag` during the current frame.
+    window_drag_requested: bool,
+
+    /// A warning was logged because a paint callback could not be executed.
+    paint_callback_warned: bool,
+
+    /// Timings of the recent frames, returned by `frame_stats`.
+    frame_timings: FrameTimings,
+
+    /// The area of the text cursor set by calling `set_ime_cursor_area` during the current frame.
+    ime_cursor_area: Option<egui::Rect>,
+
+    /// The backend presents frames in sync with the display.
+    vsync: bool,
+
+    /// The title set by calling `set_title` during the current frame.
+    title: Option<String>,
+
+    /// A screenshot was requested by calling `request_screenshot` during the current frame.
+    screenshot_requested: bool,
+
+    /// The last screenshot the backend delivered, until it is taken by calling `take_screenshot`.
+    screenshot: Option<Arc<egui::ColorImage>>,
+
+    /// The opacity set by calling `set_opacity` or `BackendConfiguration::with_initial_opacity`.
+    /// None if the window is opaque.
+    opacity: Option<f32>,
+
+    /// The monitor the window is currently on, updated before each frame.
+    monitor: Option<MonitorInfo>,
+
+    /// The directory of the storage.
+    storage_dir: Option<PathBuf>,
+
+    /// Access to the clipboard of the os, created once it is first needed.
+    /// It is kept alive because on some platforms the clipboard content is lost once it is dropped.
+    #[cfg(feature = "clipboard")]
+    clipboard: Option<arboard::Clipboard>,
 }
 
 /// Wrapper for the `SoftwareBackend`
@@ -99,7 +429,106 @@ pub struct SoftwareBackendInterop<'a> {
     integration_info: &'a mut IntegrationInfo,
 
     /// Holds the storage manager if enabled.
-    storage: &'a mut Option<Box<dyn Storage>>,
+    storage: &'a mut Option<Box<dyn StorageExt>>,
+
+    /// State kept between frames.
+    state: &'a mut InteropState,
+}
+
+/// Wrapper for the eframe `Frame`
+pub struct EframeInterop<'a> {
+    /// The reference to the actual eframe frame.
+    frame: &'a mut Frame,
+
+    /// Holds the storage manager if enabled.
+    storage: &'a mut Option<Box<dyn StorageExt>>,
+
+    /// State kept between frames.
+    state: &'a mut InteropState,
+}
+
+/// Interop of a backend registered with `BackendConfiguration::register_custom_backend`.
+/// The custom backend keeps it between frames and passes it to `App::update` as `BackendInterop::Custom`.
+pub struct CustomBackendInterop {
+    /// Id the backend was registered with.
+    id: u32,
+
+    /// Backend-specific data, the app can downcast it to the type provided by the backend.
+    data: Box<dyn Any>,
+
+    /// Holds the `IntegrationInfo` which contains the frame time.
+    integration_info: IntegrationInfo,
+
+    /// State kept between frames.
+    state: InteropState,
+}
+
+impl CustomBackendInterop {
+    /// Creates the interop of the custom backend with the given id.
+    #[must_use]
+    pub fn new(id: u32, data: Box<dyn Any>) -> Self {
+        Self {
+            id,
+            data,
+            integration_info: IntegrationInfo { cpu_usage: None },
+            state: InteropState::default(),
+        }
+    }
+
+    /// Returns the id the backend was registered with.
+    #[must_use]
+    pub const fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the backend-specific data.
+    #[must_use]
+    pub fn data(&self) -> &dyn Any {
+        self.data.as_ref()
+    }
+
+    /// Returns the backend-specific data.
+    #[must_use]
+    pub fn data_mut(&mut self) -> &mut dyn Any {
+        self.data.as_mut()
+    }
+
+    /// Sets the time the last frame took, it is returned by `BackendInterop::last_frame_time`.
+    /// The backend should call this once per frame before `App::update`, it is also recorded in
+    /// `BackendInterop::frame_stats`.
+    pub fn set_last_frame_time(&mut self, frame_time: Option<Duration>) {
+        self.integration_info.cpu_usage = frame_time.as_ref().map(Duration::as_secs_f32);
+        let time = self.state.ctx.input(|i| i.time);
+        self.state
+            .frame_timings
+            .record(self.integration_info.cpu_usage, time);
+    }
+
+    /// Sets the context returned by `BackendInterop::egui_context`, the backend should call this before `App::update`.
+    pub fn set_context(&mut self, ctx: &Context) {
+        self.state.ctx.clone_from(ctx);
+    }
+
+    /// Returns true if the app called `BackendInterop::request_exit`.
+    /// The custom backend is responsible for exiting its event loop.
+    #[must_use]
+    pub const fn exit_requested(&self) -> bool {
+        self.state.exit_requested
+    }
+}
+
+impl Deref for EframeInterop<'_> {
+    type Target = Frame;
+
+    fn deref(&self) -> &Self::Target {
+        self.frame
+    }
+}
+
+impl DerefMut for EframeInterop<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.frame
+    }
 }
 
 impl Deref for SoftwareBackendInterop<'_> {
@@ -117,26 +546,83 @@ impl DerefMut for SoftwareBackendInterop<'_> {
 }
 
 impl BackendInterop<'_> {
+    /// Returns a shorter lived copy of the interop, so it can be passed to more than one callback of the app.
+    fn reborrow(&mut self) -> BackendInterop<'_> {
+        match self {
+            BackendInterop::SoftwareBackend(swb) => {
+                BackendInterop::SoftwareBackend(SoftwareBackendInterop {
+                    swb: &mut *swb.swb,
+                    integration_info: &mut *swb.integration_info,
+                    storage: &mut *swb.storage,
+                    state: &mut *swb.state,
+                })
+            }
+            BackendInterop::Eframe(efr) => BackendInterop::Eframe(EframeInterop {
+                frame: &mut *efr.frame,
+                storage: &mut *efr.storage,
+                state: &mut *efr.state,
+            }),
+            BackendInterop::Custom(custom) => BackendInterop::Custom(custom),
+        }
+    }
+
     #[must_use]
     pub const fn backend(&self) -> Backend {
         match self {
             BackendInterop::SoftwareBackend(_) => Backend::SoftwareBackend,
             BackendInterop::Eframe(_) => Backend::Eframe,
+            BackendInterop::Custom(custom) => Backend::CustomBackend(custom.id),
+        }
+    }
+
+    /// Returns the egui context of the frame, e.g. to open secondary windows with
+    /// `egui::Context::show_viewport_immediate` or `egui::Context::show_viewport_deferred`.
+    #[must_use]
+    pub const fn egui_context(&self) -> &Context {
+        match self {
+            BackendInterop::SoftwareBackend(swbi) => &swbi.state.ctx,
+            BackendInterop::Eframe(efr) => &efr.state.ctx,
+            BackendInterop::Custom(custom) => &custom.state.ctx,
+        }
+    }
+
+    /// Returns the dark or light theme preferred in the settings of the os, it is queried again on every call.
+    /// Returns None if the theme cannot be detected, see `BackendConfiguration::with_system_theme`.
+    #[must_use]
+    pub fn system_theme(&self) -> Option<SystemTheme> {
+        detect_system_theme(self.egui_context())
+    }
+
+    /// Returns true if secondary viewports are shown in their own native windows.
+    ///
+    /// The software backend only has a single window, there egui shows secondary viewports embedded
+    /// as a window inside the main window. Their viewport builders and commands have no effect there.
+    /// eframe shows them in native windows unless the platform does not support multiple windows.
+    #[must_use]
+    pub fn supports_multiple_viewports(&self) -> bool {
+        match self {
+            BackendInterop::SoftwareBackend(_) => false,
+            BackendInterop::Eframe(_) | BackendInterop::Custom(_) => {
+                !self.egui_context().embed_viewports()
+            }
         }
     }
 
+    /// Returns a human readable name of the backend.
+    /// Use `Backend::as_str` for the name that is accepted by `Backend::from_str`.
     #[must_use]
     pub const fn backend_name(&self) -> &'static str {
         match self {
             BackendInterop::SoftwareBackend(_) => "Software Backend",
             BackendInterop::Eframe(_) => "eframe",
+            BackendInterop::Custom(_) => "Custom Backend",
         }
     }
 
     #[must_use]
     #[allow(clippy::missing_const_for_fn)]
     pub fn is_web(&self) -> bool {
-        //We don't run on the web yet at all...
+        //The web is handled by the wasm implementation.
         false
     }
 
@@ -145,132 +631,2317 @@ impl BackendInterop<'_> {
         match self {
             BackendInterop::SoftwareBackend(swbi) => swbi.integration_info,
             BackendInterop::Eframe(efr) => efr.info(),
+            BackendInterop::Custom(custom) => &custom.integration_info,
         }
     }
 
+    /// Returns how long the last frame took on the CPU.
+    /// Returns None before the first frame or if the backend does not measure it.
+    #[must_use]
+    pub fn last_frame_time(&self) -> Option<Duration> {
+        self.into()
+            .cpu_usage
+            .and_then(|secs| Duration::try_from_secs_f32(secs).ok())
+    }
+
+    /// Returns the timing statistics of the recent frames, the same way for every backend.
+    /// They are recorded before every frame, including frames where the app does nothing.
+    #[must_use]
+    pub fn frame_stats(&self) -> FrameStats {
+        let state = match self {
+            BackendInterop::SoftwareBackend(swbi) => &*swbi.state,
+            BackendInterop::Eframe(efr) => &*efr.state,
+            BackendInterop::Custom(custom) => &custom.state,
+        };
+
+        state.frame_timings.stats()
+    }
+
+    /// Returns the frames per second the app could render if every frame took as long as the last frame.
+    /// This is an upper bound, as the time spent waiting for the display is not included.
+    #[must_use]
+    pub fn estimated_fps(&self) -> Option<f32> {
+        self.last_frame_time()
+            .filter(|frame_time| !frame_time.is_zero())
+            .map(|frame_time| 1.0 / frame_time.as_secs_f32())
+    }
+
+    /// Returns the storage of the app. Both backends use the same storage file.
+    /// Custom backends have no storage.
+    #[must_use]
     pub fn storage(&self) -> Option<&dyn Storage> {
-        match self {
-            BackendInterop::SoftwareBackend(swbi) => swbi.storage.as_ref().map(Box::as_ref),
-            BackendInterop::Eframe(efr) => efr.storage(),
-        }
+        let storage = match self {
+            BackendInterop::SoftwareBackend(swbi) => swbi.storage.as_deref(),
+            BackendInterop::Eframe(efr) => efr.storage.as_deref(),
+            BackendInterop::Custom(_) => None,
+        };
+
+        storage.map(|storage| storage as &dyn Storage)
     }
 
+    /// Returns the storage of the app. Both backends use the same storage file.
     pub fn storage_mut(&mut self) -> Option<&mut (dyn Storage + 'static)> {
+        self.storage_ext()
+            .map(|storage| storage as &mut (dyn Storage + 'static))
+    }
+
+    /// Returns the directory the application state is stored in.
+    /// Returns None if there is no storage or the storage was set with `BackendConfiguration::with_storage`.
+    #[must_use]
+    pub fn storage_path(&self) -> Option<&Path> {
+        let state = match self {
+            BackendInterop::SoftwareBackend(swbi) => &*swbi.state,
+            BackendInterop::Eframe(efr) => &*efr.state,
+            BackendInterop::Custom(custom) => &custom.state,
+        };
+
+        state.storage_dir.as_deref()
+    }
+
+    /// Returns which features are available with the active backend and the enabled feature flags.
+    #[must_use]
+    pub fn capabilities(&self) -> BackendCapabilities {
+        let is_eframe = matches!(self, BackendInterop::Eframe(_));
+        let is_custom = matches!(self, BackendInterop::Custom(_));
+
+        #[allow(unused_mut)]
+        let mut capabilities = BackendCapabilities {
+            has_glow: false,
+            has_wgpu: false,
+            has_persistent_storage: self.storage().is_some(),
+            has_raw_window_handle: cfg!(feature = "raw-window-handle") && is_eframe,
+            has_clipboard: cfg!(feature = "clipboard"),
+            //Both built-in backends forward the drag and drop events of winit to egui.
+            has_file_drop: !is_custom,
+        };
+
+        #[cfg(feature = "glow")]
+        if let BackendInterop::Eframe(efr) = self {
+            capabilities.has_glow = efr.gl().is_some();
+        }
+
+        #[cfg(feature = "wgpu")]
+        {
+            capabilities.has_wgpu = self.wgpu_render_state().is_some();
+        }
+
+        capabilities
+    }
+
+    /// Returns the storage with the additional functionality of `StorageExt`.
+    pub fn storage_ext(&mut self) -> Option<&mut (dyn StorageExt + 'static)> {
         match self {
-            BackendInterop::SoftwareBackend(swbi) => swbi.storage.as_mut().map(Box::as_mut),
-            BackendInterop::Eframe(efr) => efr.storage_mut(),
+            BackendInterop::SoftwareBackend(swbi) => swbi.storage.as_deref_mut(),
+            BackendInterop::Eframe(efr) => efr.storage.as_deref_mut(),
+            BackendInterop::Custom(_) => None,
         }
     }
 
     #[cfg(feature = "glow")]
-    pub fn gl(&mut self) -> Option<&std::sync::Arc<eframe::glow::Context>> {
+    pub fn gl(&mut self) -> Option<&Arc<eframe::glow::Context>> {
         match self {
-            BackendInterop::SoftwareBackend(_) => None,
+            BackendInterop::SoftwareBackend(_) | BackendInterop::Custom(_) => None,
             BackendInterop::Eframe(efr) => efr.gl(),
         }
     }
 
+    /// Returns true if `register_native_glow_texture` can register opengl textures.
+    /// This is only the case if eframe is using the glow renderer.
+    #[cfg(feature = "glow")]
+    #[must_use]
+    pub fn supports_native_gl_textures(&self) -> bool {
+        match self {
+            BackendInterop::SoftwareBackend(_) | BackendInterop::Custom(_) => false,
+            BackendInterop::Eframe(efr) => efr.frame.gl().is_some(),
+        }
+    }
+
+    /// Registers an opengl texture, so egui can paint it.
+    /// Returns None and logs a warning if the backend does not support it, see `supports_native_gl_textures`.
     #[cfg(feature = "glow")]
     pub fn register_native_glow_texture(
         &mut self,
         native: eframe::glow::Texture,
-    ) -> egui::TextureId {
+    ) -> Option<egui::TextureId> {
         match self {
-            BackendInterop::SoftwareBackend(_) => egui::TextureId::User(0), //DUMMY
-            BackendInterop::Eframe(efr) => efr.register_native_glow_texture(native),
+            BackendInterop::Eframe(efr) if efr.frame.gl().is_some() => {
+                Some(efr.register_native_glow_texture(native))
+            }
+            _ => {
+                log::warn!(
+                    "The {} backend cannot register native opengl textures",
+                    self.backend()
+                );
+                None
+            }
         }
     }
-}
 
-/// App traits
-pub trait App {
-    /// The update loop
-    fn update(&mut self, context: &Context, backend: BackendInterop<'_>);
+    /// Returns true if the renderer executes paint callbacks, see `add_paint_callback`.
+    /// eframe executes glow callbacks with the glow renderer and wgpu callbacks with the wgpu renderer.
+    /// The software backend and custom backends do not execute any paint callbacks.
+    #[must_use]
+    pub fn supports_paint_callbacks(&self) -> bool {
+        match self {
+            BackendInterop::SoftwareBackend(_) | BackendInterop::Custom(_) => false,
+            BackendInterop::Eframe(efr) => {
+                #[allow(unused_mut)]
+                let mut supported = false;
+
+                #[cfg(feature = "glow")]
+                {
+                    supported |= efr.frame.gl().is_some();
+                }
 
-    /// This function is called once when the application exists.
-    /// It is NOT called when using eframe with the wgpu backend.
-    fn on_exit(&mut self) {}
+                #[cfg(feature = "wgpu")]
+                {
+                    supported |= efr.frame.wgpu_render_state().is_some();
+                }
 
-    /// This function is called before `on_exit` and allows you to save state
-    /// It might be called periodically too
-    fn save(&mut self, storage: &mut dyn Storage) {
-        _ = storage;
+                _ = efr;
+                supported
+            }
+        }
     }
-}
 
-/// Wrapper struct for a local app state.
-struct AppWrapper<T: App>(T, Option<Box<dyn Storage>>, IntegrationInfo);
+    /// Returns true if the renderer executes this paint callback.
+    fn supports_paint_callback(&self, callback: &egui::PaintCallback) -> bool {
+        let BackendInterop::Eframe(efr) = self else {
+            return false;
+        };
 
-impl<T: App> eframe::App for AppWrapper<T> {
-    fn update(&mut self, ctx: &Context, frame: &mut Frame) {
-        self.0.update(ctx, BackendInterop::Eframe(frame));
-    }
+        #[cfg(feature = "glow")]
+        if efr.frame.gl().is_some() && callback.callback.is::<eframe::egui_glow::CallbackFn>() {
+            return true;
+        }
 
-    fn save(&mut self, storage: &mut dyn Storage) {
-        self.0.save(storage);
-    }
+        #[cfg(feature = "wgpu")]
+        if efr.frame.wgpu_render_state().is_some()
+            && callback.callback.is::<eframe::egui_wgpu::Callback>()
+        {
+            return true;
+        }
 
-    #[cfg(feature = "glow")]
-    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
-        self.0.on_exit();
+        _ = (efr, callback);
+        false
     }
 
-    #[cfg(not(feature = "glow"))]
-    fn on_exit(&mut self) {
-        self.0.on_exit()
-    }
-}
+    /// Adds the paint callback to the painter if the renderer executes it, otherwise `fallback` is called
+    /// with the painter and the rect of the callback to paint a replacement with egui shapes.
+    ///
+    /// The software backend does not expose its frame buffer, so the fallback cannot draw into it directly.
+    /// To draw pixels rendered on the cpu, upload them with `register_texture` and paint the texture in the fallback.
+    /// A warning is logged the first time the fallback is used.
+    pub fn add_paint_callback(
+        &mut self,
+        painter: &egui::Painter,
+        callback: egui::PaintCallback,
+        fallback: impl FnOnce(&egui::Painter, egui::Rect),
+    ) {
+        if self.supports_paint_callback(&callback) {
+            painter.add(callback);
+            return;
+        }
+
+        if !std::mem::replace(&mut self.state_mut().paint_callback_warned, true) {
+            log::warn!(
+                "The {} backend cannot execute the paint callback, painting the fallback instead",
+                self.backend()
+            );
+        }
+
+        fallback(painter, callback.rect);
+    }
+
+    /// Uploads an image from cpu memory as a texture egui can paint, this works the same with every backend.
+    ///
+    /// The texture is freed once the last clone of the handle is dropped.
+    /// To stream frames, e.g. from a camera or a video decoder, keep the handle and replace the image
+    /// with `egui::TextureHandle::set` instead of registering a new texture every frame.
+    /// The software backend keeps the image in cpu memory, so uploading it only copies the pixels.
+    pub fn register_texture(
+        &self,
+        name: &str,
+        image: egui::ColorImage,
+        options: egui::TextureOptions,
+    ) -> egui::TextureHandle {
+        self.egui_context().load_texture(name, image, options)
+    }
+
+    /// Returns the wgpu render state if eframe is using the wgpu renderer.
+    #[cfg(feature = "wgpu")]
+    #[must_use]
+    pub fn wgpu_render_state(&self) -> Option<&eframe::egui_wgpu::RenderState> {
+        match self {
+            BackendInterop::SoftwareBackend(_) | BackendInterop::Custom(_) => None,
+            BackendInterop::Eframe(efr) => efr.wgpu_render_state(),
+        }
+    }
+
+    /// Registers a wgpu texture, so egui can paint it.
+    /// Returns None and logs a warning if there is no wgpu render state, see `wgpu_render_state`.
+    #[cfg(feature = "wgpu")]
+    pub fn register_wgpu_texture(
+        &mut self,
+        texture: &eframe::wgpu::Texture,
+    ) -> Option<egui::TextureId> {
+        let Some(render_state) = self.wgpu_render_state() else {
+            log::warn!(
+                "The {} backend cannot register native wgpu textures",
+                self.backend()
+            );
+            return None;
+        };
+
+        Some(render_state.renderer.write().register_native_texture(
+            &render_state.device,
+            &texture.create_view(&eframe::wgpu::TextureViewDescriptor::default()),
+            eframe::wgpu::FilterMode::Linear,
+        ))
+    }
+
+    /// Returns the handle of the native window.
+    ///
+    /// Returns None when running the software backend. `egui_software_backend` keeps its winit window private
+    /// and only passes its frame timing to the app, so there is no window to get the handle from.
+    /// Custom backends do not pass a window either.
+    ///
+    /// The handle is only valid while the window exists, it must not be kept beyond the current frame.
+    /// Most platforms only allow using it on the main thread, which is the thread `update` is called on.
+    #[cfg(feature = "raw-window-handle")]
+    #[must_use]
+    pub fn window_handle(&self) -> Option<raw_window_handle::WindowHandle<'_>> {
+        use raw_window_handle::HasWindowHandle;
+        match self {
+            BackendInterop::SoftwareBackend(_) | BackendInterop::Custom(_) => None,
+            BackendInterop::Eframe(efr) => efr.frame.window_handle().ok(),
+        }
+    }
+
+    /// Returns the handle of the native display.
+    /// Returns None when running the software backend or a custom backend, see `window_handle`.
+    ///
+    /// Like the window handle, it must not be kept beyond the current frame or used on another thread.
+    #[cfg(feature = "raw-window-handle")]
+    #[must_use]
+    pub fn display_handle(&self) -> Option<raw_window_handle::DisplayHandle<'_>> {
+        use raw_window_handle::HasDisplayHandle;
+        match self {
+            BackendInterop::SoftwareBackend(_) | BackendInterop::Custom(_) => None,
+            BackendInterop::Eframe(efr) => efr.frame.display_handle().ok(),
+        }
+    }
+
+    /// Requests the application to exit after the current frame.
+    /// `save` and `on_exit` are still called like when the user closes the window.
+    /// `on_close_requested` is not called for this request.
+    pub const fn request_exit(&mut self) {
+        self.state_mut().exit_requested = true;
+    }
+
+    /// Sets the cursor shown for the current frame.
+    /// Both backends apply it through `Context::set_cursor_icon` once `update` returns,
+    /// so it takes precedence over the cursors set by widgets during the frame.
+    pub const fn set_cursor(&mut self, cursor: egui::CursorIcon) {
+        let state = self.state_mut();
+        state.cursor = cursor;
+        state.cursor_set = true;
+    }
+
+    /// Enters or leaves fullscreen once `update` returns.
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        let state = self.state_mut();
+        if state.fullscreen.is_none() {
+            log::warn!(
+                "The backend does not report whether it is fullscreen, it might not support fullscreen."
+            );
+        }
+
+        state.requested_fullscreen = Some(fullscreen);
+    }
+
+    /// Returns true if the window is fullscreen.
+    /// Returns false if the backend does not report whether it is fullscreen.
+    #[must_use]
+    pub const fn is_fullscreen(&self) -> bool {
+        let fullscreen = match self {
+            BackendInterop::SoftwareBackend(swbi) => swbi.state.fullscreen,
+            BackendInterop::Eframe(efr) => efr.state.fullscreen,
+            BackendInterop::Custom(custom) => custom.state.fullscreen,
+        };
+
+        matches!(fullscreen, Some(true))
+    }
+
+    /// Minimizes the window once `update` returns.
+    pub const fn minimize(&mut self) {
+        self.state_mut().requested_minimized = Some(true);
+    }
+
+    /// Maximizes the window once `update` returns.
+    pub const fn maximize(&mut self) {
+        self.state_mut().requested_maximized = Some(true);
+    }
+
+    /// Restores the window from being minimized or maximized once `update` returns.
+    pub const fn restore(&mut self) {
+        let state = self.state_mut();
+        state.requested_minimized = Some(false);
+        state.requested_maximized = Some(false);
+    }
+
+    /// Returns true if the window is maximized.
+    /// Returns false if the backend does not report whether it is maximized.
+    #[must_use]
+    pub const fn is_maximized(&self) -> bool {
+        let maximized = match self {
+            BackendInterop::SoftwareBackend(swbi) => swbi.state.maximized,
+            BackendInterop::Eframe(efr) => efr.state.maximized,
+            BackendInterop::Custom(custom) => custom.state.maximized,
+        };
+
+        matches!(maximized, Some(true))
+    }
+
+    /// Returns true if the window is minimized.
+    /// Returns false if the backend does not report whether it is minimized.
+    #[must_use]
+    pub const fn is_minimized(&self) -> bool {
+        let minimized = match self {
+            BackendInterop::SoftwareBackend(swbi) => swbi.state.minimized,
+            BackendInterop::Eframe(efr) => efr.state.minimized,
+            BackendInterop::Custom(custom) => custom.state.minimized,
+        };
+
+        matches!(minimized, Some(true))
+    }
+
+    /// Resizes the inner area of the window to the size in points once `update` returns.
+    ///
+    /// eframe and the software backend apply the request to their winit window and return true.
+    /// Custom backends do not receive the request, for them this returns false.
+    /// Sizes that are not positive and finite are ignored and return false.
+    pub fn set_inner_size(&mut self, size: egui::Vec2) -> bool {
+        if !(size.is_finite() && size.x > 0.0 && size.y > 0.0) {
+            log::warn!("Ignoring invalid inner size {size:?}");
+            return false;
+        }
+
+        self.state_mut().requested_inner_size = Some(size);
+        self.handles_window_commands()
+    }
+
+    /// Asks the os to draw the attention of the user to the window once `update` returns,
+    /// e.g. by flashing its taskbar entry. `egui::UserAttentionType::Reset` cancels the request.
+    ///
+    /// Only eframe performs the request and returns true.
+    /// The software backend ignores it and custom backends do not receive it, for them this returns false.
+    pub const fn request_user_attention(&mut self, attention: egui::UserAttentionType) -> bool {
+        if !matches!(self, BackendInterop::Eframe(_)) {
+            return false;
+        }
+
+        self.state_mut().requested_user_attention = Some(attention);
+        true
+    }
+
+    /// Lets the user move the window with the mouse, e.g. from a custom title bar of an undecorated window.
+    /// Call this in the frame the primary mouse button was pressed, the drag ends when it is released.
+    /// Returns false if the backend cannot perform it, see `request_user_attention`.
+    pub const fn start_window_drag(&mut self) -> bool {
+        if !matches!(self, BackendInterop::Eframe(_)) {
+            return false;
+        }
+
+        self.state_mut().window_drag_requested = true;
+        true
+    }
+
+    /// Returns true if the requests to change the window are applied once `update` returns.
+    const fn handles_window_commands(&self) -> bool {
+        !matches!(self, BackendInterop::Custom(_))
+    }
+
+    /// Returns true if the backend waits for the vertical sync of the display before presenting a frame.
+    /// This is the vsync eframe was launched with, see `BackendConfiguration::with_vsync`.
+    /// The software backend and custom backends do not report vsync, for them this returns false.
+    #[must_use]
+    pub const fn vsync_enabled(&self) -> bool {
+        match self {
+            BackendInterop::SoftwareBackend(swbi) => swbi.state.vsync,
+            BackendInterop::Eframe(efr) => efr.state.vsync,
+            BackendInterop::Custom(custom) => custom.state.vsync,
+        }
+    }
+
+    /// Enables or disables vsync. No backend can change vsync while the app is running,
+    /// so a warning is logged if the requested value differs from `vsync_enabled`.
+    /// Use `BackendConfiguration::with_vsync` to set it when the backend is launched.
+    pub fn set_vsync(&mut self, enabled: bool) {
+        if enabled != self.vsync_enabled() {
+            log::warn!(
+                "The {} backend cannot change vsync while the app is running",
+                self.backend()
+            );
+        }
+    }
+
+    /// Places the candidate window of the input method next to the area of the text cursor, in points.
+    ///
+    /// This also enables the input method for the frame. It has to be called every frame while text is typed,
+    /// like egui's own text edits do. Both backends pass the area to `winit::window::Window::set_ime_cursor_area`.
+    /// It is only needed for custom text input, it overrides the area of a focused egui text edit.
+    pub const fn set_ime_cursor_area(&mut self, rect: egui::Rect) {
+        self.state_mut().ime_cursor_area = Some(rect);
+    }
+
+    /// Places the candidate window of the input method at the position of the text cursor, in points.
+    /// See `set_ime_cursor_area`.
+    pub const fn set_ime_position(&mut self, position: egui::Pos2) {
+        self.set_ime_cursor_area(egui::Rect::from_min_max(position, position));
+    }
+
+    /// Shows or hides the title bar and the borders of the window once `update` returns.
+    /// Both backends apply this to their winit window. Use `start_window_drag` to move a window without title bar,
+    /// which only works on eframe.
+    pub const fn set_decorations(&mut self, decorations: bool) {
+        self.state_mut().requested_decorations = Some(decorations);
+    }
+
+    /// Keeps the window above all other windows or makes it a normal window again once `update` returns.
+    pub const fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.state_mut().requested_window_level = Some(if always_on_top {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        });
+    }
+
+    /// Sets the number of physical pixels per point once `update` returns.
+    /// Values outside the range (0, 10] are ignored.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        if !is_valid_scale_factor(scale_factor) {
+            log::warn!("Ignoring invalid scale factor {scale_factor}");
+            return;
+        }
+
+        self.state_mut().requested_scale_factor = Some(scale_factor);
+    }
+
+    /// Sets the zoom factor of egui once `update` returns, the number of physical pixels per point
+    /// becomes the native scale factor of the window multiplied by the zoom factor.
+    /// Values outside the range (0, 10] are ignored.
+    ///
+    /// The zoom is applied by egui itself, so every backend tessellates and rasterizes the ui again
+    /// at the new number of pixels per point. The software backend does not scale the pixels of the frame.
+    /// The zoom factor is saved in the storage, see `BackendConfiguration::with_persist_zoom_factor`.
+    pub fn set_zoom_factor(&mut self, zoom_factor: f32) {
+        if !is_valid_scale_factor(zoom_factor) {
+            log::warn!("Ignoring invalid zoom factor {zoom_factor}");
+            return;
+        }
+
+        self.state_mut().requested_zoom_factor = Some(zoom_factor);
+    }
+
+    /// Returns the zoom factor of egui, 1.0 unless it was changed.
+    #[must_use]
+    pub fn zoom_factor(&self) -> f32 {
+        self.egui_context().zoom_factor()
+    }
+
+    /// Returns the number of physical pixels per point, this includes the native scale factor and the zoom factor.
+    #[must_use]
+    pub fn pixels_per_point(&self) -> f32 {
+        self.egui_context().pixels_per_point()
+    }
+
+    /// Sets the opacity of the window, it is clamped to the range [0, 1].
+    ///
+    /// Neither eframe nor the software backend expose `Window::set_opacity` of winit,
+    /// so eframe applies the opacity to the color the window is cleared with each frame.
+    /// Only the parts of the window the app does not paint over become transparent.
+    /// The window must have been created transparent, see `BackendConfiguration::with_initial_opacity`.
+    /// Some compositors ignore transparency entirely. The software backend does not support transparency.
+    pub fn set_opacity(&mut self, alpha: f32) {
+        let opacity = clamp_opacity(alpha);
+        if opacity.is_some() && !matches!(self, BackendInterop::Eframe(_)) {
+            log::warn!(
+                "The {} backend does not support transparency",
+                self.backend()
+            );
+        }
+
+        self.state_mut().opacity = opacity;
+    }
+
+    /// Returns the opacity of the window last set by calling `set_opacity`. 1.0 if the window is opaque.
+    #[must_use]
+    pub fn opacity(&self) -> f32 {
+        let opacity = match self {
+            BackendInterop::SoftwareBackend(swbi) => swbi.state.opacity,
+            BackendInterop::Eframe(efr) => efr.state.opacity,
+            BackendInterop::Custom(custom) => custom.state.opacity,
+        };
+
+        opacity.unwrap_or(1.0)
+    }
+
+    /// Sets the title of the window once `update` returns.
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.state_mut().title = Some(title.into());
+    }
+
+    /// Requests a screenshot of the window once `update` returns, it is returned by `take_screenshot`.
+    ///
+    /// The screenshot is taken by the backend after the frame is painted, so it is usually available in the next frame.
+    /// The image is in physical pixels, `egui::ColorImage::region` with the pixels per point of the frame crops it.
+    /// eframe takes the screenshot when it handles `egui::ViewportCommand::Screenshot`.
+    /// The software backend does not expose its frame buffer, so the frame is rendered a second time into the image.
+    /// Custom backends only deliver a screenshot if they handle `egui::ViewportCommand::Screenshot`.
+    pub const fn request_screenshot(&mut self) {
+        self.state_mut().screenshot_requested = true;
+    }
+
+    /// Returns the last screenshot requested by calling `request_screenshot`. None if it was not delivered yet.
+    /// Each screenshot is only returned once.
+    pub const fn take_screenshot(&mut self) -> Option<Arc<egui::ColorImage>> {
+        self.state_mut().screenshot.take()
+    }
+
+    /// Returns the monitor the window is currently on, as reported by egui.
+    /// None before egui reported it.
+    ///
+    /// The other connected monitors cannot be listed, neither eframe nor the software backend expose the monitors of winit.
+    #[must_use]
+    pub fn current_monitor(&self) -> Option<MonitorInfo> {
+        match self {
+            BackendInterop::SoftwareBackend(swbi) => swbi.state.monitor.clone(),
+            BackendInterop::Eframe(efr) => efr.state.monitor.clone(),
+            BackendInterop::Custom(custom) => custom.state.monitor.clone(),
+        }
+    }
+
+    /// Returns the cursor last set by calling `set_cursor`.
+    #[must_use]
+    pub const fn get_cursor(&self) -> egui::CursorIcon {
+        match self {
+            BackendInterop::SoftwareBackend(swbi) => swbi.state.cursor,
+            BackendInterop::Eframe(efr) => efr.state.cursor,
+            BackendInterop::Custom(custom) => custom.state.cursor,
+        }
+    }
+
+    /// Stores the backend that should be used on the next launch, None clears the preference.
+    /// The preference is only used if `BackendConfiguration::with_persist_backend_choice` is enabled.
+    /// This does nothing if there is no storage.
+    pub fn set_preferred_backend(&mut self, backend: Option<Backend>) {
+        if let Some(storage) = self.storage_mut() {
+            storage.set_string(
+                PREFERRED_BACKEND_KEY,
+                backend
+                    .map(|backend| backend.to_string())
+                    .unwrap_or_default(),
+            );
+        }
+    }
+
+    /// Returns the text in the clipboard of the os.
+    /// Returns None if the clipboard is empty, contains no text or cannot be accessed.
+    #[cfg(feature = "clipboard")]
+    pub fn clipboard_text(&mut self) -> Option<String> {
+        self.clipboard()?
+            .get_text()
+            .inspect_err(|e| log::debug!("Failed to read the clipboard err={e}"))
+            .ok()
+    }
+
+    /// Puts the text into the clipboard of the os.
+    #[cfg(feature = "clipboard")]
+    pub fn set_clipboard_text(&mut self, text: impl Into<String>) {
+        if let Some(clipboard) = self.clipboard()
+            && let Err(e) = clipboard.set_text(text.into())
+        {
+            log::warn!("Failed to write the clipboard err={e}");
+        }
+    }
+
+    /// Returns the clipboard of the os, creating it if this is the first access.
+    #[cfg(feature = "clipboard")]
+    fn clipboard(&mut self) -> Option<&mut arboard::Clipboard> {
+        let state = self.state_mut();
+        if state.clipboard.is_none() {
+            state.clipboard = arboard::Clipboard::new()
+                .inspect_err(|e| log::warn!("Failed to access the clipboard err={e}"))
+                .ok();
+        }
+
+        state.clipboard.as_mut()
+    }
+
+    /// Returns the report of why the current backend was selected.
+    /// This is the same as calling `selection_report`.
+    #[must_use]
+    pub fn selection_report(&self) -> Option<BackendSelectionReport> {
+        selection_report()
+    }
+
+    /// Shows information for debugging: the backend and why it was selected, the frame timings,
+    /// the storage and the renderer of eframe. Rows that do not apply to the backend or the enabled features are omitted.
+    ///
+    /// This only adds a few labels to the ui, so it is cheap enough to keep in release builds,
+    /// e.g. in an `egui::Window` that is toggled with a shortcut.
+    pub fn show_debug_ui(&mut self, ui: &mut egui::Ui) {
+        let mut rows = vec![("Backend", self.backend().to_string())];
+        if let Some(report) = self.selection_report() {
+            rows.push(("Selection reason", format!("{:?}", report.reason)));
+            if !report.detail.is_empty() {
+                rows.push(("Selection details", report.detail));
+            }
+        }
+
+        rows.push((
+            "Storage",
+            self.storage_path()
+                .map_or_else(|| String::from("-"), |path| path.display().to_string()),
+        ));
+
+        if let Some(storage) = self.storage_ext() {
+            rows.push(("Unsaved changes", storage.is_dirty().to_string()));
+        }
+
+        rows.extend(self.renderer_info());
+        let stats = self.frame_stats();
+
+        egui::Grid::new("egui_backend_selector_debug_ui")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, value) in rows {
+                    ui.label(name);
+                    ui.label(value);
+                    ui.end_row();
+                }
+
+                show_frame_stats(ui, &stats);
+            });
+    }
+
+    /// Returns the name and version of the renderer eframe is using. Empty for other backends.
+    fn renderer_info(&self) -> Vec<(&'static str, String)> {
+        #[allow(unused_mut)]
+        let mut info = Vec::new();
+        let BackendInterop::Eframe(efr) = self else {
+            return info;
+        };
+
+        #[cfg(feature = "glow")]
+        if let Some(gl) = efr.frame.gl() {
+            use eframe::glow::HasContext;
+
+            //The context is current while the app is updated.
+            let (renderer, version) = unsafe {
+                (
+                    gl.get_parameter_string(eframe::glow::RENDERER),
+                    gl.get_parameter_string(eframe::glow::VERSION),
+                )
+            };
+            info.push(("OpenGL renderer", renderer));
+            info.push(("OpenGL version", version));
+        }
+
+        #[cfg(feature = "wgpu")]
+        if let Some(render_state) = efr.frame.wgpu_render_state() {
+            let adapter = render_state.adapter.get_info();
+            info.push(("wgpu adapter", adapter.name));
+            info.push(("wgpu backend", format!("{:?}", adapter.backend)));
+        }
+
+        _ = efr;
+        info
+    }
+
+    /// Returns the state kept between frames.
+    const fn state_mut(&mut self) -> &mut InteropState {
+        match self {
+            BackendInterop::SoftwareBackend(swbi) => swbi.state,
+            BackendInterop::Eframe(efr) => efr.state,
+            BackendInterop::Custom(custom) => &mut custom.state,
+        }
+    }
+}
+
+/// App traits
+pub trait App {
+    /// The update loop
+    fn update(&mut self, context: &Context, backend: BackendInterop<'_>);
+
+    /// This function is called once when the application exists.
+    /// It is NOT called when using eframe with the wgpu backend.
+    fn on_exit(&mut self) {}
+
+    /// This function is called before `on_exit` and allows you to save state
+    /// It might be called periodically too
+    fn save(&mut self, storage: &mut dyn Storage) {
+        _ = storage;
+    }
+
+    /// This function is called when the user requested to close the window, for example by clicking the close button.
+    /// Returning false cancels the close request, which allows showing a "save before exit?" dialog.
+    /// `on_exit` is only called once the close request was allowed.
+    ///
+    /// This is only called on eframe. The software backend exits as soon as the window is asked to close,
+    /// without running another frame, so the close request cannot be seen or cancelled there.
+    fn on_close_requested(&mut self, context: &Context) -> bool {
+        _ = context;
+        true
+    }
+
+    /// This function is called when files were dropped onto the window, right before `update` is called.
+    /// It is only called in frames in which files were dropped.
+    fn on_file_drop(
+        &mut self,
+        context: &Context,
+        backend: BackendInterop<'_>,
+        paths: Vec<PathBuf>,
+    ) {
+        _ = (context, backend, paths);
+    }
+
+    /// This function is called when the files dragged over the window changed, right before `update` is called.
+    /// Once the files are no longer dragged over the window, this is called with an empty vec.
+    /// Not all platforms report the hovered files.
+    fn on_file_hover(
+        &mut self,
+        context: &Context,
+        backend: BackendInterop<'_>,
+        paths: Vec<PathBuf>,
+    ) {
+        _ = (context, backend, paths);
+    }
+
+    /// This function is called when the window gained the keyboard focus, right before `update` is called.
+    /// It is not called for the first frame, even if the window is focused then.
+    fn on_focus_gained(&mut self, context: &Context) {
+        _ = context;
+    }
+
+    /// This function is called when the window lost the keyboard focus, right before `update` is called.
+    fn on_focus_lost(&mut self, context: &Context) {
+        _ = context;
+    }
+
+    /// This function is called when the inner size of the window changed, right before `update` is called.
+    /// The new size is in physical pixels. It is not called for the first frame.
+    fn on_resize(&mut self, context: &Context, new_size_in_pixels: egui::Vec2) {
+        _ = (context, new_size_in_pixels);
+    }
+
+    /// This function is called once after the app is created, before the first `update`.
+    /// The context is fully configured at this point, e.g. the restored egui memory and the scale factor are applied,
+    /// so this is the place for one-time setup like fonts, visuals or installing image loaders.
+    fn post_init(&mut self, ctx: &Context) {
+        _ = ctx;
+    }
+
+    /// Returns how often the state of the app is saved while it is running, both backends call `save` this often.
+    /// `save` is called once more when the app exits.
+    /// This is called again after every save, so the interval can change while the app is running.
+    /// Intervals shorter than one second are treated as one second.
+    fn auto_save_interval(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    /// Called every frame before the input is processed, including frames without events.
+    /// The app can change the input, e.g. to handle global shortcuts or to inject synthetic input for automation.
+    ///
+    /// eframe calls this before the input is passed to egui.
+    /// The software backend does not expose its input before the frame starts, so there it is called at the
+    /// start of the frame instead and the changed events and modifiers replace the ones of the current frame.
+    /// Other changes, like the screen rect, take effect on the software backend only once egui processes them again.
+    fn raw_input_hook(&mut self, ctx: &Context, raw_input: &mut egui::RawInput) {
+        _ = (ctx, raw_input);
+    }
+
+    /// Returns the color the window is cleared with before each frame, in normalized gamma space.
+    /// It is visible behind translucent egui content and, with a transparent viewport, behind the window.
+    /// The default is the same as in eframe.
+    fn clear_color(&self, visuals: &egui::Visuals) -> [f32; 4] {
+        _ = visuals;
+        default_clear_color()
+    }
+}
+
+/// Returns the clear color eframe uses by default.
+fn default_clear_color() -> [f32; 4] {
+    egui::Color32::from_rgba_unmultiplied(12, 12, 12, 180).to_normalized_gamma_f32()
+}
+
+/// Size changes of the window smaller than this many pixels are considered floating point noise.
+const RESIZE_EPSILON: f32 = 0.5;
+
+/// Files dragged over or dropped onto the window since the previous frame.
+struct FileEvents {
+    /// The hovered files, if they changed.
+    hovered: Option<Vec<PathBuf>>,
+
+    /// The dropped files.
+    dropped: Vec<PathBuf>,
+}
+
+/// Storage key of the x coordinate of the window position.
+const WINDOW_X_KEY: &str = "__egui_backend_selector::window_x";
+
+/// Storage key of the y coordinate of the window position.
+const WINDOW_Y_KEY: &str = "__egui_backend_selector::window_y";
+
+/// Storage key of the window width.
+const WINDOW_WIDTH_KEY: &str = "__egui_backend_selector::window_width";
+
+/// Storage key of the window height.
+const WINDOW_HEIGHT_KEY: &str = "__egui_backend_selector::window_height";
+
+/// Storage key of whether the window was maximized.
+const WINDOW_MAXIMIZED_KEY: &str = "__egui_backend_selector::window_maximized";
+
+/// Storage key of the zoom factor of egui.
+const ZOOM_FACTOR_KEY: &str = "__egui_backend_selector::zoom_factor";
+
+/// Storage key of the egui memory, this is the key eframe uses in its own storage.
+#[cfg(feature = "persistence")]
+const EGUI_MEMORY_KEY: &str = "egui";
+
+/// Saved coordinates and sizes with an absolute value larger than this are considered garbage.
+const MAX_WINDOW_COORDINATE: f32 = 65536.0;
+
+/// Position and size of the window in points.
+#[derive(Debug, Copy, Clone)]
+struct WindowGeometry {
+    /// The outer position of the window.
+    position: egui::Pos2,
+
+    /// The inner size of the window.
+    size: egui::Vec2,
+
+    /// Was the window maximized? The position and size are the ones it is restored to.
+    maximized: bool,
+}
+
+impl WindowGeometry {
+    /// Returns the current geometry of the window.
+    /// While the window is maximized the previous position and size are kept.
+    /// Returns the previous geometry if the window is minimized or fullscreen, or the backend does not report the geometry.
+    fn current(ctx: &Context, previous: Option<Self>) -> Option<Self> {
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            if viewport.minimized.unwrap_or(false) || viewport.fullscreen.unwrap_or(false) {
+                return previous;
+            }
+
+            if viewport.maximized.unwrap_or(false) {
+                return previous.map(|previous| Self {
+                    maximized: true,
+                    ..previous
+                });
+            }
+
+            let (Some(outer_rect), Some(inner_rect)) = (viewport.outer_rect, viewport.inner_rect)
+            else {
+                return previous;
+            };
+
+            Some(Self {
+                position: outer_rect.min,
+                size: inner_rect.size(),
+                maximized: false,
+            })
+        })
+    }
+
+    /// Loads the geometry from the storage. Returns None if it is missing or implausible.
+    fn load(storage: &dyn Storage) -> Option<Self> {
+        let read = |key: &str| {
+            storage
+                .get_string(key)?
+                .parse::<f32>()
+                .ok()
+                .filter(|value| value.is_finite() && value.abs() <= MAX_WINDOW_COORDINATE)
+        };
+
+        let geometry = Self {
+            position: egui::pos2(read(WINDOW_X_KEY)?, read(WINDOW_Y_KEY)?),
+            size: egui::vec2(read(WINDOW_WIDTH_KEY)?, read(WINDOW_HEIGHT_KEY)?),
+            //Missing if it was saved by an older version.
+            maximized: storage.get_string(WINDOW_MAXIMIZED_KEY).as_deref() == Some("true"),
+        };
+
+        if geometry.size.x < 1.0 || geometry.size.y < 1.0 {
+            return None;
+        }
+
+        Some(geometry)
+    }
+
+    /// Saves the geometry in the storage.
+    fn save(self, storage: &mut dyn Storage) {
+        storage.set_string(WINDOW_X_KEY, self.position.x.to_string());
+        storage.set_string(WINDOW_Y_KEY, self.position.y.to_string());
+        storage.set_string(WINDOW_WIDTH_KEY, self.size.x.to_string());
+        storage.set_string(WINDOW_HEIGHT_KEY, self.size.y.to_string());
+        storage.set_string(WINDOW_MAXIMIZED_KEY, self.maximized.to_string());
+    }
+
+    /// Applies the geometry to the viewport.
+    const fn apply(self, viewport: &mut ViewportBuilder) {
+        viewport.position = Some(self.position);
+        viewport.inner_size = Some(self.size);
+        viewport.maximized = Some(self.maximized);
+    }
+
+    /// Returns false if the window is known to not be on any monitor, e.g. because the monitor it was saved on
+    /// was unplugged. The backends only report the monitor the window is on, which is None if there is none.
+    /// Returns None until the backend reports the position of the window.
+    fn is_on_screen(ctx: &Context) -> Option<bool> {
+        ctx.input(|i| {
+            let viewport = i.viewport();
+            viewport.outer_rect?;
+            Some(viewport.monitor_size.is_some())
+        })
+    }
+}
+
+/// Options of the `AppWrapper` taken from the `BackendConfiguration`.
+#[derive(Debug, Copy, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+struct AppWrapperOptions {
+    /// Save the window position and size in the storage.
+    persist_window_geometry: bool,
+
+    /// Save the egui memory in the storage.
+    persist_egui_memory: bool,
+
+    /// Save the zoom factor in the storage.
+    persist_zoom_factor: bool,
+
+    /// Overrides `App::auto_save_interval` if set.
+    auto_save_interval: Option<Duration>,
+
+    /// How often changed values in the storage are written to disk. None only writes them when the app is saved.
+    storage_flush_interval: Option<Duration>,
+
+    /// The opacity of the window when it is created.
+    opacity: Option<f32>,
+
+    /// The maximum number of frames per second. None is unlimited.
+    max_fps: Option<f32>,
+
+    /// The window geometry loaded from the storage and applied to the viewport.
+    window_geometry: Option<WindowGeometry>,
+
+    /// The backend was launched with vsync.
+    vsync: bool,
+}
+
+impl AppWrapperOptions {
+    /// Takes the options from the configuration.
+    const fn new(config: &BackendConfiguration) -> Self {
+        Self {
+            persist_window_geometry: config.persist_window_geometry,
+            persist_egui_memory: !config.egui_memory_persistence_disabled,
+            persist_zoom_factor: !config.zoom_factor_persistence_disabled,
+            auto_save_interval: config.auto_save_interval,
+            storage_flush_interval: config.storage_flush_interval,
+            opacity: config.initial_opacity,
+            max_fps: config.max_fps,
+            window_geometry: None,
+            vsync: false,
+        }
+    }
+}
+
+/// Wrapper struct for a local app state.
+#[allow(clippy::struct_excessive_bools)]
+struct AppWrapper<T: App> {
+    /// The actual app.
+    app: T,
+
+    /// Holds the storage manager if enabled.
+    storage: Option<Box<dyn StorageExt>>,
+
+    /// Holds the `IntegrationInfo` for the software backend.
+    integration_info: IntegrationInfo,
+
+    /// State of the interop kept between frames.
+    state: InteropState,
+
+    /// Save the window position and size in the storage.
+    persist_window_geometry: bool,
+
+    /// The last known position and size of the window while it was neither maximized, minimized nor fullscreen.
+    window_geometry: Option<WindowGeometry>,
+
+    /// The window geometry was restored from the storage and it was not yet checked whether it is on screen.
+    check_window_on_screen: bool,
+
+    /// The files that were dragged over the window in the previous frame.
+    hovered_files: Vec<PathBuf>,
+
+    /// Was the window focused in the previous frame? None before the first frame.
+    focused: Option<bool>,
+
+    /// The inner size of the window in pixels in the previous frame. None before the first frame.
+    size_in_pixels: Option<egui::Vec2>,
+
+    /// Overrides `App::auto_save_interval` if set.
+    auto_save_interval: Option<Duration>,
+
+    /// The context whose memory is saved in the storage. None if the egui memory is not persisted.
+    egui_memory_ctx: Option<Context>,
+
+    /// Save the zoom factor in the storage.
+    persist_zoom_factor: bool,
+
+    /// When the state was last saved by the software backend.
+    last_save: std::time::Instant,
+
+    /// How often changed values in the storage are written to disk. None only writes them when the app is saved.
+    storage_flush_interval: Option<Duration>,
+
+    /// When the storage was last written to disk because of `storage_flush_interval`.
+    last_flush: std::time::Instant,
+
+    /// The shortest time a frame may take. None if the frame rate is unlimited.
+    min_frame_time: Option<Duration>,
+
+    /// When the software backend started the previous frame. None before the first frame.
+    last_frame_start: Option<std::time::Instant>,
+}
+
+impl<T: App> AppWrapper<T> {
+    /// Constructor
+    fn new(
+        mut app: T,
+        storage: Option<Box<dyn StorageExt>>,
+        storage_dir: Option<PathBuf>,
+        options: AppWrapperOptions,
+        ctx: &Context,
+    ) -> Self {
+        let egui_memory_ctx = options.persist_egui_memory.then(|| ctx.clone());
+        if let Some(ctx) = egui_memory_ctx.as_ref()
+            && let Some(storage) = storage.as_deref()
+        {
+            load_egui_memory(ctx, storage);
+        }
+
+        if options.persist_zoom_factor
+            && let Some(storage) = storage.as_deref()
+            && let Some(zoom_factor) = load_zoom_factor(storage)
+        {
+            ctx.set_zoom_factor(zoom_factor);
+        }
+
+        app.post_init(ctx);
+
+        Self {
+            app,
+            //Without a storage there is no directory to show.
+            state: InteropState {
+                storage_dir: storage_dir.filter(|_| storage.is_some()),
+                ctx: ctx.clone(),
+                opacity: options.opacity,
+                vsync: options.vsync,
+                ..InteropState::default()
+            },
+            storage,
+            integration_info: IntegrationInfo { cpu_usage: None },
+            persist_window_geometry: options.persist_window_geometry,
+            window_geometry: options.window_geometry,
+            check_window_on_screen: options.window_geometry.is_some(),
+            hovered_files: Vec::new(),
+            focused: None,
+            size_in_pixels: None,
+            auto_save_interval: options.auto_save_interval,
+            last_save: std::time::Instant::now(),
+            storage_flush_interval: options.storage_flush_interval,
+            last_flush: std::time::Instant::now(),
+            egui_memory_ctx,
+            persist_zoom_factor: options.persist_zoom_factor,
+            min_frame_time: options
+                .max_fps
+                .map(|max_fps| Duration::from_secs_f32(1.0 / max_fps)),
+            last_frame_start: None,
+        }
+    }
+
+    /// Returns the clear color of the app with the opacity set by `BackendInterop::set_opacity` applied.
+    fn clear_color(&self, visuals: &egui::Visuals) -> [f32; 4] {
+        let mut color = self.app.clear_color(visuals);
+        if let Some(opacity) = self.state.opacity {
+            color[3] = opacity;
+        }
+
+        color
+    }
+
+    /// Calls the raw input hook of the app with the input of the current frame and applies the changed events.
+    /// The software backend has no hook before the frame starts, so this is called at the start of the frame.
+    fn run_raw_input_hook(&mut self, ctx: &Context) {
+        let mut raw_input = ctx.input(|i| i.raw.clone());
+        self.app.raw_input_hook(ctx, &mut raw_input);
+        ctx.input_mut(|input| {
+            input.events.clone_from(&raw_input.events);
+            input.modifiers = raw_input.modifiers;
+            input.raw = raw_input;
+        });
+    }
+
+    /// Fills the window with the clear color of the app.
+    /// The software backend has no clear color, so it is painted on the background layer before the app updates.
+    /// Nothing is painted if the app keeps the default clear color, so such apps look like they always did.
+    //The default color is compared exactly, it is the value returned by the default implementation.
+    #[allow(clippy::float_cmp)]
+    fn paint_clear_color(&self, ctx: &Context) {
+        let color = self.clear_color(&ctx.style().visuals);
+        if color == default_clear_color() {
+            return;
+        }
+
+        //The components are in the range [0, 1], the conversion can't truncate.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let [r, g, b, a] = color.map(|component| (component.clamp(0.0, 1.0) * 255.0).round() as u8);
+
+        ctx.layer_painter(egui::LayerId::background()).rect_filled(
+            ctx.content_rect(),
+            0.0,
+            egui::Color32::from_rgba_unmultiplied(r, g, b, a),
+        );
+    }
+
+    /// Returns how often the state of the app is saved, at least `MIN_AUTO_SAVE_INTERVAL`.
+    fn auto_save_interval(&self) -> Duration {
+        self.auto_save_interval
+            .unwrap_or_else(|| self.app.auto_save_interval())
+            .max(MIN_AUTO_SAVE_INTERVAL)
+    }
+
+    /// Sleeps until the shortest time a frame may take elapsed since the start of the previous frame.
+    /// Sleeping would block the event loop of eframe, so this is only used by the software backend.
+    fn limit_frame_rate(&mut self) {
+        let Some(min_frame_time) = self.min_frame_time else {
+            return;
+        };
+
+        if let Some(last_frame_start) = self.last_frame_start
+            && let Some(remaining) = min_frame_time.checked_sub(last_frame_start.elapsed())
+        {
+            std::thread::sleep(remaining);
+        }
+
+        self.last_frame_start = Some(std::time::Instant::now());
+    }
+
+    /// Saves the state if the auto save interval elapsed since the last save.
+    /// eframe does this itself, so this is only used by the software backend.
+    fn auto_save(&mut self) {
+        if self.storage.is_none() || self.last_save.elapsed() < self.auto_save_interval() {
+            return;
+        }
+
+        self.save_to_storage();
+        self.last_save = std::time::Instant::now();
+    }
+
+    /// Writes the values the app changed in the storage to disk once the flush interval elapsed.
+    /// The storage only writes the file if a value changed since the previous flush.
+    fn flush_storage(&mut self) {
+        let Some(flush_interval) = self.storage_flush_interval else {
+            return;
+        };
+
+        if self.last_flush.elapsed() < flush_interval {
+            return;
+        }
+
+        if let Some(store) = self.storage.as_mut() {
+            store.flush();
+        }
+
+        self.last_flush = std::time::Instant::now();
+    }
+
+    /// Saves the app state and the window geometry to the storage and writes it to disk.
+    fn save_to_storage(&mut self) {
+        if let Some(mut store) = self.storage.take() {
+            self.save_all(store.as_mut());
+            store.flush();
+            self.storage = Some(store);
+        }
+    }
+
+    /// Blocks until the storage has been written to disk, so nothing is lost when the process exits.
+    fn wait_for_storage(&self) {
+        if let Some(store) = self.storage.as_ref() {
+            store.wait_for_flush();
+        }
+    }
+
+    /// Saves the app state, the window geometry and the egui memory if enabled.
+    fn save_all(&mut self, storage: &mut dyn Storage) {
+        self.app.save(storage);
+
+        if let Some(ctx) = self.egui_memory_ctx.as_ref() {
+            save_egui_memory(ctx, storage);
+        }
+
+        if self.persist_zoom_factor {
+            storage.set_string(ZOOM_FACTOR_KEY, self.state.ctx.zoom_factor().to_string());
+        }
+
+        if self.persist_window_geometry
+            && let Some(geometry) = self.window_geometry
+        {
+            geometry.save(storage);
+        }
+    }
+
+    /// Called before each update of the app.
+    fn before_update(&mut self, ctx: &Context) -> FileEvents {
+        self.state.ctx.clone_from(ctx);
+        self.state.monitor = current_monitor(ctx);
+        if let Some(screenshot) = take_screenshot_event(ctx) {
+            self.state.screenshot = Some(screenshot);
+        }
+
+        (
+            self.state.fullscreen,
+            self.state.maximized,
+            self.state.minimized,
+        ) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (viewport.fullscreen, viewport.maximized, viewport.minimized)
+        });
+        self.handle_close_request(ctx);
+        self.handle_focus_change(ctx);
+        self.handle_resize(ctx);
+        self.take_file_events(ctx)
+    }
+
+    /// Calls the resize callback of the app if the inner size of the window changed since the previous frame.
+    fn handle_resize(&mut self, ctx: &Context) {
+        let size_in_pixels = ctx.input(|i| i.viewport_rect().size() * i.pixels_per_point());
+        if let Some(previous) = self.size_in_pixels.replace(size_in_pixels)
+            && (previous - size_in_pixels).abs().max_elem() > RESIZE_EPSILON
+        {
+            self.app.on_resize(ctx, size_in_pixels);
+        }
+    }
+
+    /// Calls the focus callbacks of the app if the focus of the window changed since the previous frame.
+    fn handle_focus_change(&mut self, ctx: &Context) {
+        let focused = ctx.input(|i| i.focused);
+        match self.focused.replace(focused) {
+            Some(false) if focused => self.app.on_focus_gained(ctx),
+            Some(true) if !focused => self.app.on_focus_lost(ctx),
+            _ => {}
+        }
+    }
+
+    /// Collects the files dragged over or dropped onto the window.
+    fn take_file_events(&mut self, ctx: &Context) -> FileEvents {
+        let (hovered, dropped) = ctx.input(|i| {
+            let hovered = i
+                .raw
+                .hovered_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect::<Vec<_>>();
+
+            let dropped = i
+                .raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect::<Vec<_>>();
+
+            (hovered, dropped)
+        });
+
+        let hovered = if hovered == self.hovered_files {
+            None
+        } else {
+            self.hovered_files.clone_from(&hovered);
+            Some(hovered)
+        };
+
+        FileEvents { hovered, dropped }
+    }
+
+    /// Calls the file callbacks of the app, followed by its update function.
+    fn update_app(app: &mut T, ctx: &Context, mut backend: BackendInterop<'_>, files: FileEvents) {
+        if let Some(paths) = files.hovered {
+            app.on_file_hover(ctx, backend.reborrow(), paths);
+        }
+
+        if !files.dropped.is_empty() {
+            app.on_file_drop(ctx, backend.reborrow(), files.dropped);
+        }
+
+        app.update(ctx, backend);
+    }
+
+    /// Asks the app whether a pending close request should be allowed and cancels it if not.
+    fn handle_close_request(&mut self, ctx: &Context) {
+        if self.state.exit_requested {
+            //The app requested to exit itself, there is nothing to veto.
+            return;
+        }
+
+        if ctx.input(|i| i.viewport().close_requested()) && !self.app.on_close_requested(ctx) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+        }
+    }
+
+    /// Called after each update of the app.
+    fn after_update(&mut self, ctx: &Context) {
+        if self.persist_window_geometry {
+            self.window_geometry = WindowGeometry::current(ctx, self.window_geometry);
+        }
+
+        if self.check_window_on_screen
+            && let Some(on_screen) = WindowGeometry::is_on_screen(ctx)
+        {
+            self.check_window_on_screen = false;
+            if !on_screen {
+                log::info!("The restored window is not on any monitor, moving it to the origin");
+                ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::Pos2::ZERO));
+            }
+        }
+
+        if std::mem::take(&mut self.state.cursor_set) {
+            ctx.set_cursor_icon(self.state.cursor);
+        }
+
+        if let Some(fullscreen) = self.state.requested_fullscreen.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(fullscreen));
+        }
+
+        if let Some(window_level) = self.state.requested_window_level.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(window_level));
+        }
+
+        if let Some(decorations) = self.state.requested_decorations.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(decorations));
+        }
+
+        //Both backends apply the viewport commands to their winit window.
+        if let Some(minimized) = self.state.requested_minimized.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(minimized));
+        }
+
+        if let Some(maximized) = self.state.requested_maximized.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(maximized));
+        }
+
+        if let Some(scale_factor) = self.state.requested_scale_factor.take() {
+            ctx.set_pixels_per_point(scale_factor);
+        }
+
+        if let Some(zoom_factor) = self.state.requested_zoom_factor.take() {
+            ctx.set_zoom_factor(zoom_factor);
+        }
+
+        if let Some(size) = self.state.requested_inner_size.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+        }
+
+        if let Some(attention) = self.state.requested_user_attention.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::RequestUserAttention(attention));
+        }
+
+        if let Some(rect) = self.state.ime_cursor_area.take() {
+            ctx.output_mut(|o| {
+                o.ime = Some(egui::output::IMEOutput {
+                    rect,
+                    cursor_rect: rect,
+                });
+            });
+        }
+
+        if std::mem::take(&mut self.state.window_drag_requested) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+        }
+
+        if let Some(title) = self.state.title.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title));
+        }
+
+        if std::mem::take(&mut self.state.screenshot_requested) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
+        }
+
+        if self.state.exit_requested {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+}
+
+impl<T: App> eframe::App for AppWrapper<T> {
+    fn auto_save_interval(&self) -> Duration {
+        Self::auto_save_interval(self)
+    }
+
+    fn clear_color(&self, visuals: &egui::Visuals) -> [f32; 4] {
+        Self::clear_color(self, visuals)
+    }
+
+    fn raw_input_hook(&mut self, ctx: &Context, raw_input: &mut egui::RawInput) {
+        self.app.raw_input_hook(ctx, raw_input);
+    }
+
+    fn update(&mut self, ctx: &Context, frame: &mut Frame) {
+        self.state
+            .frame_timings
+            .record(frame.info().cpu_usage, ctx.input(|i| i.time));
+        let files = self.before_update(ctx);
+        Self::update_app(
+            &mut self.app,
+            ctx,
+            BackendInterop::Eframe(EframeInterop {
+                frame,
+                storage: &mut self.storage,
+                state: &mut self.state,
+            }),
+            files,
+        );
+        self.after_update(ctx);
+        if let Some(min_frame_time) = self.min_frame_time
+            && !self.state.exit_requested
+        {
+            ctx.request_repaint_after(min_frame_time);
+        }
+
+        self.flush_storage();
+    }
+
+    fn save(&mut self, storage: &mut dyn Storage) {
+        //The app state is kept in our own storage, so it is the same for both backends.
+        _ = storage;
+        self.save_to_storage();
+    }
+
+    fn persist_egui_memory(&self) -> bool {
+        //The egui memory is kept in our own storage too, see save_all.
+        false
+    }
+
+    #[cfg(feature = "glow")]
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.app.on_exit();
+        self.wait_for_storage();
+    }
+
+    #[cfg(not(feature = "glow"))]
+    fn on_exit(&mut self) {
+        self.app.on_exit();
+        self.wait_for_storage();
+    }
+}
+
 impl<T: App> egui_software_backend::App for AppWrapper<T> {
     fn update(&mut self, ctx: &Context, software_backend: &mut SoftwareBackend) {
-        self.2.cpu_usage = software_backend.last_frame_time().map(|a| a.as_secs_f32());
+        self.limit_frame_rate();
+        self.integration_info.cpu_usage =
+            software_backend.last_frame_time().map(|a| a.as_secs_f32());
+        self.state
+            .frame_timings
+            .record(self.integration_info.cpu_usage, ctx.input(|i| i.time));
+        self.run_raw_input_hook(ctx);
+        let files = self.before_update(ctx);
+        if let Some(screenshot) = ScreenshotPlugin::take_screenshot(ctx) {
+            self.state.screenshot = Some(screenshot);
+        }
+
+        self.paint_clear_color(ctx);
+
+        Self::update_app(
+            &mut self.app,
+            ctx,
+            BackendInterop::SoftwareBackend(SoftwareBackendInterop {
+                swb: software_backend,
+                integration_info: &mut self.integration_info,
+                storage: &mut self.storage,
+                state: &mut self.state,
+            }),
+            files,
+        );
+        if self.state.screenshot_requested {
+            ScreenshotPlugin::request(ctx);
+        }
+
+        self.after_update(ctx);
+        if self.state.exit_requested {
+            //The software backend exits on ViewportCommand::Close without calling on_exit.
+            egui_software_backend::App::on_exit(self, ctx);
+            return;
+        }
+
+        self.auto_save();
+        self.flush_storage();
+    }
+
+    fn on_exit(&mut self, _ctx: &Context) {
+        self.save_to_storage();
+        self.app.on_exit();
+        self.wait_for_storage();
+    }
+}
+
+/// App handed to the software backend.
+/// The software backend has no way to fail app creation, so a failed app is represented by its own variant.
+enum SoftwareBackendApp<T: App> {
+    /// The app factory succeeded. The wrapper is boxed, it is much larger than the failed variant.
+    Created(Box<AppWrapper<T>>),
+
+    /// The app factory failed, the window is closed as soon as possible.
+    Failed,
+}
+
+impl<T: App> egui_software_backend::App for SoftwareBackendApp<T> {
+    fn update(&mut self, ctx: &Context, software_backend: &mut SoftwareBackend) {
+        match self {
+            Self::Created(app) => {
+                egui_software_backend::App::update(app.as_mut(), ctx, software_backend);
+            }
+            Self::Failed => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+        }
+    }
+
+    fn on_exit(&mut self, ctx: &Context) {
+        if let Self::Created(app) = self {
+            egui_software_backend::App::on_exit(app.as_mut(), ctx);
+        }
+    }
+}
+
+#[derive(Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct BackendConfiguration {
+    /// Egui `ViewportBuilder`. This struct is shared by both backends and contains
+    /// 90% of the settings one wishes to set.
+    /// This viewport will always take precedence over the viewport set in the other structs.
+    viewport: ViewportBuilder,
+
+    /// The eframe specific options if any.
+    eframe_options: Option<NativeOptions>,
+
+    /// The software backend specific options if any.
+    software_backend_options: Option<SoftwareBackendAppConfiguration>,
+
+    /// Options that affect how the backend is determined.
+    detection: DetectionOptions,
+
+    /// Relaunch with the software backend if eframe fails to initialize.
+    eframe_fallback: bool,
+
+    /// Save the window position and size and restore them on the next launch.
+    persist_window_geometry: bool,
+
+    /// Use the software backend if the previous run with eframe did not exit cleanly.
+    crash_protection: bool,
+
+    /// File format of the storage used by the software backend.
+    storage_format: StorageFormat,
+
+    /// Directory of the storage. None uses the default directory of eframe.
+    storage_path: Option<PathBuf>,
+
+    /// Name of the storage file relative to the storage directory. None uses the name of the storage format.
+    storage_filename: Option<PathBuf>,
+
+    /// Store the data next to the executable if no storage path is set.
+    portable_storage: bool,
+
+    /// Do not import the state stored by eframe in its default directory.
+    eframe_import_disabled: bool,
+
+    /// Values of the storage longer than this number of bytes are compressed. None disables compression.
+    storage_compression_threshold: Option<usize>,
+
+    /// Compression of the whole storage file.
+    storage_file_compression: StorageCompression,
+
+    /// Creates the storage used instead of the file storage. None uses the file storage.
+    storage_factory: Option<StorageFactory>,
+
+    /// How often the state of the app is saved. None uses `App::auto_save_interval`.
+    auto_save_interval: Option<Duration>,
+
+    /// How often changed values in the storage are written to disk. None only writes them when the app is saved.
+    storage_flush_interval: Option<Duration>,
+
+    /// The opacity of the window when it is created. None if the window is opaque.
+    initial_opacity: Option<f32>,
+
+    /// Apply the theme of the os to egui before the app is created.
+    system_theme: bool,
+
+    /// Expose the ui to assistive technology through AccessKit.
+    accessibility: bool,
+
+    /// Overrides `NativeOptions::vsync` if set.
+    vsync: Option<bool>,
+
+    /// Do not save the egui memory in the storage.
+    egui_memory_persistence_disabled: bool,
+
+    /// Do not save the zoom factor in the storage.
+    zoom_factor_persistence_disabled: bool,
+
+    /// The maximum number of frames per second. None is unlimited.
+    max_fps: Option<f32>,
+
+    /// Migrations of the persisted data, run in order on startup.
+    storage_migrations: Vec<Arc<dyn StorageMigration>>,
+
+    /// Called once the backend is selected, before the event loop is started.
+    /// Shared between clones of the configuration, it is only ever called once.
+    backend_selected_callback: Option<BackendSelectedCallback>,
+
+    /// Backends registered with `register_custom_backend` and their launch functions.
+    custom_backends: Vec<(u32, CustomBackendLaunchFn)>,
+
+    /// Backends that may be used. None allows all backends.
+    allowed_backends: Option<Vec<Backend>>,
+
+    /// Order in which the backends are tried if the detected backend is not allowed.
+    preferred_order: Vec<Backend>,
+
+    /// Select the backend with the `--egui-backend` command line argument.
+    parse_cli_args: bool,
+
+    /// Use the backend stored with `BackendInterop::set_preferred_backend`.
+    persist_backend_choice: bool,
+
+    /// Number of physical pixels per point set before the app is created. None uses the scale factor of the os.
+    scale_factor: Option<f32>,
+
+    /// Select the backend again if `run_app` is called after the event loop exited.
+    reselect_on_relaunch: bool,
+
+    /// Function that is called before the built-in detection and may select the backend.
+    backend_override_fn: Option<fn() -> Option<Backend>>,
+
+    /// Refuse to run if another instance of the app is running.
+    #[cfg(feature = "single-instance")]
+    single_instance: bool,
+}
+
+/// Callback that is called once the backend is selected.
+type BackendSelectedCallback = Arc<Mutex<Option<Box<dyn FnOnce(Backend) + Send>>>>;
+
+/// Creates the storage set with `BackendConfiguration::with_storage`.
+type StorageFactory = Arc<dyn Fn() -> Box<dyn StorageExt> + Send + Sync>;
+
+/// Launch function of a custom backend, it receives the configuration and runs the event loop.
+type CustomBackendLaunch =
+    Box<dyn FnOnce(BackendConfiguration) -> Result<(), Box<dyn Error>> + Send>;
+
+/// Launch function of a custom backend, shared between clones of the configuration like the callback.
+type CustomBackendLaunchFn = Arc<Mutex<Option<CustomBackendLaunch>>>;
+
+impl BackendConfiguration {
+    /// Creates a configuration for all backends.
+    /// Note that the `viewport_builder` argument is used instead of the viewports configured inside the backend configurations.
+    #[must_use]
+    pub fn new(
+        viewport_builder: ViewportBuilder,
+        native_options: NativeOptions,
+        software_backend_options: SoftwareBackendAppConfiguration,
+    ) -> Self {
+        Self {
+            viewport: viewport_builder,
+            eframe_options: Some(native_options),
+            software_backend_options: Some(software_backend_options),
+            ..Self::default()
+        }
+    }
+
+    /// Enables or disables the fallback to the software backend if eframe fails to initialize opengl or wgpu.
+    /// This is disabled by default.
+    ///
+    /// The fallback cannot retry in the same process: eframe has already created the winit event loop when it fails,
+    /// winit does not allow creating a second one, and the software backend always creates its own.
+    /// The fallback therefore relaunches the current executable with the same arguments and the software backend forced.
+    /// The relaunched process calls the app factory with the same viewport and storage,
+    /// `run_app` returns once it has exited and fails with `FallbackFailed` if it did not exit successfully.
+    #[must_use]
+    pub const fn with_eframe_fallback(mut self, eframe_fallback: bool) -> Self {
+        self.eframe_fallback = eframe_fallback;
+        self
+    }
+
+    /// Enables or disables saving the egui memory in the storage. This is enabled by default, like in eframe.
+    /// The memory contains the positions of windows, the state of collapsing headers and the like.
+    /// It is saved under the key eframe uses and restored after the app factory returned,
+    /// so it is kept when the selected backend changes between runs.
+    ///
+    /// This requires the persistence feature, without it this does nothing.
+    #[must_use]
+    pub const fn with_persist_egui_memory(mut self, persist_egui_memory: bool) -> Self {
+        self.egui_memory_persistence_disabled = !persist_egui_memory;
+        self
+    }
+
+    /// Enables or disables saving the zoom factor of egui in the storage. This is enabled by default.
+    /// The zoom factor is restored before `App::post_init` is called, regardless of the selected backend.
+    /// See `BackendInterop::set_zoom_factor`.
+    #[must_use]
+    pub const fn with_persist_zoom_factor(mut self, persist_zoom_factor: bool) -> Self {
+        self.zoom_factor_persistence_disabled = !persist_zoom_factor;
+        self
+    }
+
+    /// Enables or disables saving the window position, size and whether it is maximized in the storage.
+    /// This is disabled by default. It works the same with both backends, like `NativeOptions::persist_window` of eframe.
+    /// The saved values are applied to the viewport on the next launch and take precedence over
+    /// the values set in the viewport. Saved values that are implausible are ignored.
+    /// If the backend reports that the restored window is not on any monitor, it is moved to the origin.
+    /// Nothing is saved if there is no storage.
+    ///
+    /// This requires the persistence feature, without it this does nothing.
+    #[must_use]
+    pub const fn with_persist_window_geometry(mut self, persist_window_geometry: bool) -> Self {
+        self.persist_window_geometry = persist_window_geometry;
+        self
+    }
+
+    /// Enables or disables the crash protection. This is enabled by default.
+    ///
+    /// While the app is running, a marker file recording the backend is kept in the storage directory of the app.
+    /// The marker is removed once the app exits cleanly.
+    /// If the marker of a previous run with eframe is still present on launch,
+    /// then eframe most likely crashed and the software backend is used instead.
+    /// A backend chosen explicitly, e.g. with `overwrite_backend` or the command line argument,
+    /// takes precedence over this. Call `clear_crash_marker` to reset this.
+    /// The marker follows `with_storage_path` and `with_portable_storage` like the storage does.
+    ///
+    /// This requires the persistence feature, without it this does nothing.
+    #[must_use]
+    pub const fn with_crash_protection(mut self, crash_protection: bool) -> Self {
+        self.crash_protection = crash_protection;
+        self
+    }
+
+    /// Starts the window maximized.
+    /// This changes the viewport, which is used for both backends.
+    #[must_use]
+    pub const fn with_maximized(mut self, maximized: bool) -> Self {
+        self.viewport.maximized = Some(maximized);
+        self
+    }
+
+    /// Limits the number of frames per second, None is unlimited. This is the default.
+    /// eframe is asked to repaint once the shortest frame time elapsed, it still repaints sooner on input
+    /// or when the app calls `request_repaint`. Its event loop is never blocked, so input is handled right away.
+    /// The software backend sleeps before a frame until the shortest frame time elapsed since the previous frame.
+    /// Values that are not positive are ignored.
+    #[must_use]
+    pub fn with_max_fps(mut self, max_fps: Option<f32>) -> Self {
+        self.max_fps = max_fps.filter(|max_fps| max_fps.is_finite() && *max_fps > 0.0);
+        self
+    }
+
+    /// Sets the opacity of the window when it is created, it is clamped to the range [0, 1].
+    /// A value below 1 creates the window transparent, which is required to change the opacity later
+    /// with `BackendInterop::set_opacity`. See there for the limitations.
+    #[must_use]
+    pub fn with_initial_opacity(mut self, alpha: f32) -> Self {
+        self.initial_opacity = clamp_opacity(alpha);
+        if self.initial_opacity.is_some() {
+            self.viewport.transparent = Some(true);
+        }
+
+        self
+    }
+
+    /// Switches egui to the dark or light theme preferred in the settings of the os before the app is created.
+    /// This is disabled by default.
+    ///
+    /// With the `system-theme` feature the os is queried directly, which also works on the software backend.
+    /// Otherwise only the theme reported by the backend is used, usually only eframe reports it.
+    /// The theme is not changed again while the app is running, see `BackendInterop::system_theme`.
+    #[must_use]
+    pub const fn with_system_theme(mut self, system_theme: bool) -> Self {
+        self.system_theme = system_theme;
+        self
+    }
+
+    /// Exposes the ui to screen readers and other assistive technology through AccessKit. This is disabled by default.
+    ///
+    /// This requires the `accessibility` feature, which enables the AccessKit integration of eframe.
+    /// eframe then builds the accessibility tree from the first frame on, not only once assistive technology asks for it.
+    /// The software backend has no AccessKit integration, a warning is logged when it is launched with this enabled.
+    #[must_use]
+    pub const fn with_accessibility(mut self, accessibility: bool) -> Self {
+        self.accessibility = accessibility;
+        self
+    }
+
+    /// Waits for the vertical sync of the display before presenting a frame, this is enabled by default.
+    /// This overrides `NativeOptions::vsync`. eframe cannot change it while the app is running.
+    /// The software backend has no vsync, see `BackendInterop::vsync_enabled`.
+    #[must_use]
+    pub const fn with_vsync(mut self, vsync: bool) -> Self {
+        self.vsync = Some(vsync);
+        self
+    }
+
+    /// Shows or hides the title bar and the borders of the window, they are shown by default.
+    /// This changes the viewport, which is used for both backends.
+    /// See `BackendInterop::set_decorations` to change it while the app is running.
+    #[must_use]
+    pub const fn with_decorations(mut self, decorations: bool) -> Self {
+        self.viewport.decorations = Some(decorations);
+        self
+    }
+
+    /// Keeps the window above all other windows.
+    /// This changes the window level of the viewport, which is used for both backends.
+    #[must_use]
+    pub const fn with_always_on_top(mut self, always_on_top: bool) -> Self {
+        self.viewport.window_level = Some(if always_on_top {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        });
+        self
+    }
+
+    /// Sets the icon of the window for both backends.
+    /// `rgba` contains the pixels row by row, with 4 bytes per pixel.
+    ///
+    /// # Errors
+    /// If the width or height is 0 or if the length of `rgba` is not width * height * 4.
+    pub fn with_icon(mut self, width: u32, height: u32, rgba: Vec<u8>) -> Result<Self, IconError> {
+        self.viewport.icon = Some(Arc::new(icon_data(width, height, rgba)?));
+        Ok(self)
+    }
+
+    /// Selects the backend again if `run_app` is called again after its event loop exited.
+    /// By default, the backend that was used by the previous call is used again.
+    /// Note that only eframe can be relaunched, see the relaunching section of `run_app`.
+    #[must_use]
+    pub const fn with_reselect_on_relaunch(mut self, reselect_on_relaunch: bool) -> Self {
+        self.reselect_on_relaunch = reselect_on_relaunch;
+        self
+    }
+
+    /// Sets the number of physical pixels per point, overriding the scale factor of the os.
+    /// It is set before the app factory is called. `run_app` fails if it is not in the range (0, 10].
+    #[must_use]
+    pub const fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = Some(scale_factor);
+        self
+    }
+
+    /// Sets the file format of the storage. Both backends use the same storage file.
+    /// If there is no file of this format yet, the state stored in another format is loaded
+    /// and written in this format on the next flush.
+    #[must_use]
+    pub const fn with_storage_format(mut self, storage_format: StorageFormat) -> Self {
+        self.storage_format = storage_format;
+        self
+    }
+
+    /// Sets the directory where the application state is stored.
+    /// By default, the platform-specific directory of eframe is used.
+    ///
+    /// The storage file is created inside this directory.
+    /// When running eframe, eframe keeps its own state in `eframe.ron` inside this directory,
+    /// unless `NativeOptions::persistence_path` is set.
+    #[must_use]
+    pub fn with_storage_path(mut self, storage_path: impl Into<PathBuf>) -> Self {
+        self.storage_path = Some(storage_path.into());
+        self
+    }
+
+    /// Sets the name of the storage file, relative to the storage directory. By default, it is named after
+    /// the storage format, e.g. `app.ron`.
+    ///
+    /// The name may contain subdirectories, they are created when the storage is opened.
+    /// The state stored in another format is only converted if the default name is used.
+    #[must_use]
+    pub fn with_storage_filename(mut self, storage_filename: impl Into<PathBuf>) -> Self {
+        self.storage_filename = Some(storage_filename.into());
+        self
+    }
+
+    /// Stores the application state in `<app_name>-data` next to the executable. This is disabled by default.
+    ///
+    /// If the directory of the executable is not writable, the default directory of eframe is used instead.
+    /// `BackendConfiguration::with_storage_path` takes precedence over this.
+    #[must_use]
+    pub const fn with_portable_storage(mut self, portable_storage: bool) -> Self {
+        self.portable_storage = portable_storage;
+        self
+    }
+
+    /// Sets how often the state of the app is saved while it is running, overriding `App::auto_save_interval`.
+    /// The state is only saved if there is a storage. It is saved once more when the app exits.
+    /// Intervals shorter than one second are treated as one second.
+    #[must_use]
+    pub const fn with_auto_save_interval(mut self, auto_save_interval: Duration) -> Self {
+        self.auto_save_interval = Some(auto_save_interval);
+        self
+    }
+
+    /// Writes values the app changed in the storage to disk at most once per interval, at the end of a frame.
+    /// By default, they are only written when the app is saved, see `with_auto_save_interval`.
+    ///
+    /// Unlike the auto save this does not call `App::save`, it only writes what the app already put into the storage,
+    /// e.g. through `BackendInterop::storage_mut` during `update`. Nothing is written if no value changed.
+    #[must_use]
+    pub const fn with_storage_flush_interval(mut self, storage_flush_interval: Duration) -> Self {
+        self.storage_flush_interval = Some(storage_flush_interval);
+        self
+    }
+
+    /// Uses the storage returned by the factory instead of the file storage, on both backends.
+    ///
+    /// This is the storage the app factory, `App::save` and `BackendInterop::storage` see.
+    /// The factory is called whenever `run_app` opens the storage, which can happen more than once.
+    /// The storage path, format, compression and eframe import settings do not apply to it,
+    /// but eframe still keeps its own state in the storage directory.
+    /// It works without the `persistence` feature, use `MemoryStorage` to keep everything in memory.
+    #[must_use]
+    pub fn with_storage(
+        mut self,
+        factory: impl Fn() -> Box<dyn StorageExt> + Send + Sync + 'static,
+    ) -> Self {
+        self.storage_factory = Some(Arc::new(factory));
+        self
+    }
+
+    /// Compresses values of the storage that are longer than the threshold in bytes. This is disabled by default.
+    ///
+    /// The values are deflate compressed and base64 encoded, which helps with large values like the egui memory.
+    /// Values that are not compressed can always be read. Builds without the `storage-compression` feature
+    /// treat compressed values as missing, so disabling the feature again loses them.
+    #[cfg(feature = "storage-compression")]
+    #[must_use]
+    pub const fn with_storage_compression(mut self, threshold: usize) -> Self {
+        self.storage_compression_threshold = Some(threshold);
+        self
+    }
+
+    /// Compresses the whole storage file. This is disabled by default.
+    ///
+    /// Unlike `with_storage_compression`, which compresses single large values, this compresses the file after it is
+    /// serialized in the storage format. Compressed files are read regardless of this setting,
+    /// see `StorageCompression` for the compatibility with other versions of the app.
+    /// The `compression` feature is required for any compression.
+    #[must_use]
+    pub const fn with_storage_file_compression(mut self, compression: StorageCompression) -> Self {
+        self.storage_file_compression = compression;
+        self
+    }
+
+    /// Imports the state eframe stored in its default directory on the first run. This is enabled by default.
+    ///
+    /// The state is imported if the storage directory contains no storage yet,
+    /// for example when an app that used eframe directly is ported to the backend selector
+    /// or when the storage path or format is changed. The files of eframe are not modified.
+    /// Disable this if the app should start with a fresh state instead.
+    #[must_use]
+    pub const fn with_eframe_storage_import(mut self, eframe_storage_import: bool) -> Self {
+        self.eframe_import_disabled = !eframe_storage_import;
+        self
+    }
+
+    /// Restricts the backends that may be used.
+    ///
+    /// The detection still runs, but if the detected backend is not allowed,
+    /// then the first allowed backend from the preferred order is used instead.
+    /// `run_app` returns `BackendSelectorError::NoAllowedBackend` if no backend is allowed.
+    ///
+    /// This also applies if the backend was set with `overwrite_backend`.
+    #[must_use]
+    pub fn with_allowed_backends(mut self, allowed_backends: &[Backend]) -> Self {
+        self.allowed_backends = Some(allowed_backends.to_vec());
+        self
+    }
+
+    /// Sets a function that is called before the built-in detection.
+    ///
+    /// If it returns a backend, then that backend is used without running the built-in detection
+    /// or the probes registered with `register_probe`. If it returns None, the detection runs as usual.
+    /// It is not called if the backend was already selected, for example with `overwrite_backend`.
+    ///
+    /// The function is called on the main thread by `run_app` and must be fast, so it must not do blocking I/O.
+    #[must_use]
+    pub const fn with_backend_override_fn(
+        mut self,
+        backend_override_fn: fn() -> Option<Backend>,
+    ) -> Self {
+        self.backend_override_fn = Some(backend_override_fn);
+        self
+    }
+
+    /// Refuses to run if another instance of the app is running. This is disabled by default.
+    ///
+    /// The instances are identified by the app name passed to `run_app`,
+    /// which returns `BackendSelectorError::AlreadyRunning` without launching a backend if another instance holds the lock.
+    /// The lock is held until `run_app` returns. On Windows a named mutex is used,
+    /// on other platforms a lock file in the runtime directory of the user,
+    /// or in the storage directory of the app if there is no runtime directory.
+    #[cfg(feature = "single-instance")]
+    #[must_use]
+    pub const fn with_single_instance(mut self, single_instance: bool) -> Self {
+        self.single_instance = single_instance;
+        self
+    }
+
+    /// Sets the order in which the backends are tried if the detected backend is not allowed.
+    /// Backends missing from this list are tried afterward, eframe first.
+    #[must_use]
+    pub fn with_preferred_order(mut self, preferred_order: &[Backend]) -> Self {
+        self.preferred_order = preferred_order.to_vec();
+        self
+    }
+
+    /// Allows selecting the backend with the `--egui-backend=<value>` or `--egui-backend <value>` command line argument.
+    /// See `backend_from_args` for how the arguments are parsed. The arguments are not modified.
+    #[must_use]
+    pub const fn with_parse_cli_args(mut self, parse_cli_args: bool) -> Self {
+        self.parse_cli_args = parse_cli_args;
+        self
+    }
+
+    /// Uses the backend stored with `BackendInterop::set_preferred_backend` instead of the detection.
+    /// The stored preference does not take precedence over `overwrite_backend` and the command line argument.
+    /// This requires the persistence feature, without it this does nothing.
+    #[must_use]
+    pub const fn with_persist_backend_choice(mut self, persist_backend_choice: bool) -> Self {
+        self.persist_backend_choice = persist_backend_choice;
+        self
+    }
+
+    /// Returns the backend to use if the given backend was detected.
+    /// Returns None if no backend is allowed.
+    fn allowed_backend(&self, detected_backend: Backend) -> Option<Backend> {
+        let is_allowed = |backend: &Backend| {
+            let is_available = match backend {
+                Backend::CustomBackend(id) => self
+                    .custom_backends
+                    .iter()
+                    .any(|(registered, _)| registered == id),
+                _ => backend.is_available(),
+            };
+
+            is_available
+                && self
+                    .allowed_backends
+                    .as_ref()
+                    .is_none_or(|allowed_backends| allowed_backends.contains(backend))
+        };
+
+        if is_allowed(&detected_backend) {
+            return Some(detected_backend);
+        }
+
+        self.preferred_order
+            .iter()
+            .chain(Backend::ALL.iter())
+            .copied()
+            .find(is_allowed)
+    }
+
+    /// Sets the migrations of the persisted data.
+    ///
+    /// On startup, the schema version is read from the storage and the migrations are run in order.
+    /// Each migration receives the schema version returned by the previous migration.
+    /// The resulting schema version is written to the storage afterward.
+    ///
+    /// This requires the persistence feature, without it this does nothing.
+    #[must_use]
+    pub fn with_storage_migration(mut self, migrations: Vec<Box<dyn StorageMigration>>) -> Self {
+        self.storage_migrations = migrations.into_iter().map(Arc::from).collect();
+        self
+    }
+
+    /// Sets a callback that is called once the backend is selected.
+    ///
+    /// The callback is called on the main thread right before the event loop is started.
+    /// It is also called if the backend was set with `overwrite_backend`.
+    ///
+    /// If eframe fails to initialize and the eframe fallback is enabled,
+    /// then the relaunched process calls the callback a second time with the software backend.
+    #[must_use]
+    pub fn on_backend_selected(mut self, callback: impl FnOnce(Backend) + Send + 'static) -> Self {
+        self.backend_selected_callback = Some(Arc::new(Mutex::new(Some(Box::new(callback)))));
+        self
+    }
+
+    /// Registers a backend that is not built into the backend selector, for example a wrapper around SDL2.
+    ///
+    /// The custom backend is selected as `Backend::CustomBackend(id)`, for example with `overwrite_backend`
+    /// or `--egui-backend=custom-<id>`. If no backend was selected otherwise,
+    /// the first registered custom backend is used instead of the built-in detection.
+    ///
+    /// The launch function receives the configuration and runs the event loop.
+    /// It creates the app itself and passes `BackendInterop::Custom` to `App::update`.
+    /// It is only ever called once, registering the same id again replaces the launch function.
+    #[must_use]
+    pub fn register_custom_backend(
+        mut self,
+        id: u32,
+        launch_fn: impl FnOnce(Self) -> Result<(), Box<dyn Error>> + Send + 'static,
+    ) -> Self {
+        self.custom_backends
+            .retain(|(registered, _)| *registered != id);
+        self.custom_backends
+            .push((id, Arc::new(Mutex::new(Some(Box::new(launch_fn))))));
+        self
+    }
+
+    /// Takes the launch function of the custom backend. Returns None if it is not registered or was already called.
+    fn take_custom_backend(&self, id: u32) -> Option<CustomBackendLaunch> {
+        self.custom_backends
+            .iter()
+            .find(|(registered, _)| *registered == id)?
+            .1
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    /// Sets the directory where the result of the opengl probe is cached.
+    /// Passing None disables the cache, which is the default.
+    ///
+    /// This only has an effect on windows, where the probe requires creating a hidden opengl window.
+    /// The cache is discarded if the graphics driver changed or if it is older than a week.
+    #[must_use]
+    pub fn with_probe_cache_path(mut self, probe_cache_path: Option<PathBuf>) -> Self {
+        self.detection.probe_cache_path = probe_cache_path;
+        self
+    }
+
+    /// Enables or disables performing the opengl probe in a child process. This is disabled by default.
+    ///
+    /// Some broken graphics drivers crash the process when an opengl context is created.
+    /// If enabled, the current executable is spawned again to perform the probe,
+    /// and a crash of the child process results in the software backend being chosen.
+    /// The child process performs the probe once it calls `run_app` or `handle_probe_subprocess`.
+    /// You should call `handle_probe_subprocess` at the very top of your main function,
+    /// so that the child process does not do anything else.
+    ///
+    /// This only has an effect on windows, where the probe requires creating a hidden opengl window.
+    #[must_use]
+    pub const fn with_isolated_probe(mut self, isolated_probe: bool) -> Self {
+        self.detection.isolated_probe = isolated_probe;
+        self
+    }
 
-        self.0.update(
-            ctx,
-            BackendInterop::SoftwareBackend(SoftwareBackendInterop {
-                swb: software_backend,
-                integration_info: &mut self.2,
-                storage: &mut self.1,
-            }),
-        );
+    /// Ignores the cached result of the opengl probe and probes again. The new result is still cached.
+    /// This only has an effect on Windows, and only if `with_probe_cache_path` is set.
+    #[must_use]
+    pub const fn with_force_reprobe(mut self, force_reprobe: bool) -> Self {
+        self.detection.force_reprobe = force_reprobe;
+        self
     }
 
-    fn on_exit(&mut self, _ctx: &Context) {
-        if let Some(store) = self.1.as_mut() {
-            self.0.save(store.as_mut());
-            store.flush();
-        }
+    /// Sets how long the opengl probe may take before the graphics driver is assumed to hang. The default is 5 seconds.
+    ///
+    /// If the probe is isolated, then the probe process is killed and the software backend is chosen.
+    /// Otherwise, the hung probe cannot be aborted, so the process is relaunched with the software backend
+    /// and the hung process exits with the exit code of the relaunched process once it exits.
+    /// This only has an effect on Windows, where the opengl probe creates a context.
+    #[must_use]
+    pub const fn with_probe_timeout(mut self, probe_timeout: Duration) -> Self {
+        self.detection.probe_timeout = probe_timeout;
+        self
+    }
 
-        self.0.on_exit();
+    /// Uses eframe even if opengl is only provided by a software renderer like llvmpipe, softpipe or `SwiftShader`.
+    /// By default, the software backend is used in that case, as it is faster than those renderers.
+    /// This only has an effect on Windows, where the opengl probe creates a context.
+    /// On linux no context is created, the detection only checks for a gpu render node,
+    /// so a software renderer is not detected there and this is ignored.
+    #[must_use]
+    pub const fn with_allow_software_gl(mut self, allow_software_gl: bool) -> Self {
+        self.detection.allow_software_gl = allow_software_gl;
+        self
     }
-}
 
-#[derive(Default, Clone)]
-pub struct BackendConfiguration {
-    /// Egui `ViewportBuilder`. This struct is shared by both backends and contains
-    /// 90% of the settings one wishes to set.
-    /// This viewport will always take precedence over the viewport set in the other structs.
-    viewport: ViewportBuilder,
+    /// Sets the opengl version the probe requires for eframe to be selected, by default this is 3.2.
+    /// This only affects the detection probe, not which version eframe actually requires at runtime,
+    /// an application that uses newer opengl features should require the version it uses.
+    /// This only has an effect on Windows, where the opengl probe creates a context.
+    #[must_use]
+    pub const fn with_minimum_opengl_version(mut self, major: u8, minor: u8) -> Self {
+        self.detection.minimum_opengl_version = (major, minor);
+        self
+    }
 
-    /// The eframe specific options if any.
-    eframe_options: Option<NativeOptions>,
+    /// Runs the built-in detection with the options of this configuration, without selecting the backend.
+    /// Neither the probes registered with `register_probe` nor `with_backend_override_fn` are considered,
+    /// so with `with_platform_detector` the result only depends on the detector.
+    /// # Returns
+    /// This function may return None if called outside the main thread, like `get_backend`.
+    #[must_use]
+    pub fn probe_backend(&self) -> Option<BackendSelectionReport> {
+        determine_backend(&self.detection)
+    }
 
-    /// The software backend specific options if any.
-    software_backend_options: Option<SoftwareBackendAppConfiguration>,
+    /// Sets how the built-in detection queries the environment, the file system and opengl.
+    /// By default, the real system is queried with `RealPlatformDetector`.
+    /// This is meant for tests, which can check the backend selected for a fixed environment with `probe_backend`.
+    #[must_use]
+    pub fn with_platform_detector(mut self, platform_detector: Box<dyn PlatformDetector>) -> Self {
+        self.detection.platform_detector = Arc::from(platform_detector);
+        self
+    }
 }
 
-impl BackendConfiguration {
-    /// Creates a configuration for all backends.
-    /// Note that the `viewport_builder` argument is used instead of the viewports configured inside the backend configurations.
-    #[must_use]
-    pub const fn new(
-        viewport_builder: ViewportBuilder,
-        native_options: NativeOptions,
-        software_backend_options: SoftwareBackendAppConfiguration,
-    ) -> Self {
+impl Default for BackendConfiguration {
+    fn default() -> Self {
         Self {
-            viewport: viewport_builder,
-            eframe_options: Some(native_options),
-            software_backend_options: Some(software_backend_options),
+            viewport: ViewportBuilder::default(),
+            eframe_options: None,
+            software_backend_options: None,
+            detection: DetectionOptions::default(),
+            eframe_fallback: false,
+            persist_window_geometry: false,
+            crash_protection: true,
+            storage_format: StorageFormat::Ron,
+            storage_path: None,
+            storage_filename: None,
+            portable_storage: false,
+            eframe_import_disabled: false,
+            custom_backends: Vec::new(),
+            storage_compression_threshold: None,
+            storage_file_compression: StorageCompression::None,
+            storage_factory: None,
+            auto_save_interval: None,
+            storage_flush_interval: None,
+            initial_opacity: None,
+            system_theme: false,
+            accessibility: false,
+            vsync: None,
+            egui_memory_persistence_disabled: false,
+            zoom_factor_persistence_disabled: false,
+            max_fps: None,
+            storage_migrations: Vec::new(),
+            backend_selected_callback: None,
+            allowed_backends: None,
+            preferred_order: Vec::new(),
+            parse_cli_args: false,
+            persist_backend_choice: false,
+            scale_factor: None,
+            reselect_on_relaunch: false,
+            backend_override_fn: None,
+            #[cfg(feature = "single-instance")]
+            single_instance: false,
         }
     }
 }
@@ -279,8 +2950,7 @@ impl From<egui::ViewportBuilder> for BackendConfiguration {
     fn from(value: egui::ViewportBuilder) -> Self {
         Self {
             viewport: value,
-            eframe_options: None,
-            software_backend_options: None,
+            ..Self::default()
         }
     }
 }
@@ -290,7 +2960,7 @@ impl From<NativeOptions> for BackendConfiguration {
         Self {
             viewport: value.viewport.clone(),
             eframe_options: Some(value),
-            software_backend_options: None,
+            ..Self::default()
         }
     }
 }
@@ -299,112 +2969,292 @@ impl From<SoftwareBackendAppConfiguration> for BackendConfiguration {
     fn from(value: SoftwareBackendAppConfiguration) -> Self {
         Self {
             viewport: value.viewport_builder.clone(),
-
-            eframe_options: None,
             software_backend_options: Some(value),
+            ..Self::default()
         }
     }
 }
 
-/// "eframe" compatible Key Value Storage implementation.
+/// Removes the crash marker of the app, so that the next launch no longer avoids eframe
+/// because a previous run with eframe did not exit cleanly.
+///
+/// The marker is kept in the storage directory, so pass the configuration the app is launched with.
+/// See `BackendConfiguration::with_crash_protection`.
+#[cfg(feature = "persistence")]
+pub fn clear_crash_marker(app_name: &str, backend_configuration: &BackendConfiguration) {
+    if let Some(storage_dir) = storage_dir(app_name, backend_configuration) {
+        crate::crash_marker::clear(&storage_dir);
+    }
+}
+
+/// Without the persistence feature there is no crash marker, this does nothing.
+#[cfg(not(feature = "persistence"))]
+pub const fn clear_crash_marker(app_name: &str, backend_configuration: &BackendConfiguration) {
+    _ = (app_name, backend_configuration);
+}
+
+/// Returns the directory of the storage.
+/// Returns None if the default directory of eframe cannot be determined.
 #[cfg(feature = "persistence")]
-struct KVStorage {
-    /// Path to the file where we will save the data.
-    ron_file: std::path::PathBuf,
+fn storage_dir(app_name: &str, config: &BackendConfiguration) -> Option<PathBuf> {
+    if let Some(storage_path) = config.storage_path.as_ref() {
+        return Some(storage_path.clone());
+    }
+
+    if config.portable_storage {
+        if let Some(portable_dir) = portable_storage_dir(app_name) {
+            return Some(portable_dir);
+        }
 
-    /// The state that was loaded/modified and flush will write to disk.
-    kv: std::collections::HashMap<String, String>,
+        log::warn!(
+            "The directory of the executable is not writable, the application state is stored in the default directory."
+        );
+    }
+
+    eframe::storage_dir(app_name)
+}
 
-    /// Did we change anything?
-    dirty: bool,
+/// Without the persistence feature there is no storage.
+#[cfg(not(feature = "persistence"))]
+const fn storage_dir(app_name: &str, config: &BackendConfiguration) -> Option<PathBuf> {
+    _ = (app_name, &config.storage_path, config.portable_storage);
+    None
 }
 
+/// Returns the storage file eframe uses in its default directory, it is imported on the first run.
+/// Returns None if the import is disabled.
 #[cfg(feature = "persistence")]
-impl KVStorage {
-    /// Constructor
-    pub fn new(app_name: &str) -> Option<Self> {
-        let data_dir = eframe::storage_dir(app_name)?;
-        let ron_file = data_dir.join("app.ron");
-
-        let initial_data = if ron_file.exists() {
-            let file = std::fs::File::open(&ron_file)
-                .inspect_err(|e| {
-                    log::error!(
-                        "Failed to read application state. Could not read file {} err={e}",
-                        ron_file.display()
-                    );
-                })
-                .ok()?;
-
-            let reader = std::io::BufReader::new(file);
-            ron::de::from_reader(reader)
-                .inspect_err(|e| {
-                    log::error!(
-                        "Failed to read application state. File contains invalid data {} err={e}",
-                        ron_file.display()
-                    );
-                })
-                .ok()?
-        } else {
-            std::collections::HashMap::new()
-        };
+fn legacy_eframe_file(app_name: &str, config: &BackendConfiguration) -> Option<PathBuf> {
+    if config.eframe_import_disabled {
+        return None;
+    }
 
-        Some(Self {
-            ron_file,
-            kv: initial_data,
-            dirty: false,
-        })
+    Some(eframe::storage_dir(app_name)?.join(LEGACY_EFRAME_FILE_NAME))
+}
+
+/// Without the persistence feature there is no storage.
+#[cfg(not(feature = "persistence"))]
+const fn legacy_eframe_file(app_name: &str, config: &BackendConfiguration) -> Option<PathBuf> {
+    _ = (app_name, config.eframe_import_disabled);
+    None
+}
+
+/// Copies the state eframe stored itself, like the egui memory, to the new eframe state file.
+/// This is only done if the new file does not exist yet, unreadable files are skipped.
+#[cfg(feature = "persistence")]
+fn import_eframe_state(legacy_file: &Path, eframe_state_file: &Path) {
+    if eframe_state_file.exists() || !legacy_file.exists() || legacy_file == eframe_state_file {
+        return;
+    }
+
+    if let Some(parent) = eframe_state_file.parent() {
+        _ = std::fs::create_dir_all(parent);
+    }
+
+    match std::fs::copy(legacy_file, eframe_state_file) {
+        Ok(_) => log::info!("Imported the eframe state from {}", legacy_file.display()),
+        Err(e) => log::warn!(
+            "Failed to import the eframe state from {} err={e}",
+            legacy_file.display()
+        ),
     }
 }
 
+/// Returns the `<app_name>-data` directory next to the executable.
+/// Returns None if it cannot be created or is not writable.
 #[cfg(feature = "persistence")]
-impl Storage for KVStorage {
-    fn get_string(&self, key: &str) -> Option<String> {
-        self.kv.get(key).cloned()
+fn portable_storage_dir(app_name: &str) -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let dir = exe.parent()?.join(format!("{app_name}-data"));
+    std::fs::create_dir_all(&dir).ok()?;
+
+    let test_file = dir.join(".write_test");
+    std::fs::File::create(&test_file).ok()?;
+    _ = std::fs::remove_file(&test_file);
+
+    Some(dir)
+}
+
+/// Opens the storage set with `BackendConfiguration::with_storage`, or the file storage in the directory.
+/// Returns None if the file storage could not be opened.
+fn open_app_storage(
+    config: &BackendConfiguration,
+    data_dir: Option<&Path>,
+    legacy_file: Option<&Path>,
+) -> Option<Box<dyn StorageExt>> {
+    if let Some(storage_factory) = config.storage_factory.as_ref() {
+        return Some(storage_factory());
     }
 
-    fn set_string(&mut self, key: &str, value: String) {
-        self.kv.insert(key.to_string(), value);
-        self.dirty = true;
+    open_storage(
+        data_dir,
+        config.storage_filename.as_deref(),
+        config.storage_format,
+        config.storage_file_compression,
+        legacy_file,
+        config.storage_compression_threshold,
+    )
+}
+
+/// Opens the file storage used by both backends.
+/// Returns None if the storage could not be opened.
+#[cfg(feature = "persistence")]
+fn open_storage(
+    data_dir: Option<&Path>,
+    file_name: Option<&Path>,
+    format: StorageFormat,
+    file_compression: StorageCompression,
+    legacy_file: Option<&Path>,
+    compression_threshold: Option<usize>,
+) -> Option<Box<dyn StorageExt>> {
+    #[allow(unused_mut)]
+    let mut storage = KVStorage::new(data_dir?, file_name, format, file_compression, legacy_file)?;
+
+    #[cfg(feature = "storage-compression")]
+    if let Some(compression_threshold) = compression_threshold {
+        storage.set_compression_threshold(compression_threshold);
     }
 
-    fn flush(&mut self) {
-        if !self.dirty {
-            return;
-        }
+    #[cfg(not(feature = "storage-compression"))]
+    {
+        _ = compression_threshold;
+    }
 
-        let rp = self.ron_file.as_path();
+    Some(Box::new(storage))
+}
 
-        if let Some(parent) = rp.parent()
-            && !parent.exists()
-        {
-            _ = std::fs::create_dir_all(parent);
-        }
+/// Without the persistence feature there is no storage.
+#[cfg(not(feature = "persistence"))]
+const fn open_storage(
+    data_dir: Option<&Path>,
+    file_name: Option<&Path>,
+    format: StorageFormat,
+    file_compression: StorageCompression,
+    legacy_file: Option<&Path>,
+    compression_threshold: Option<usize>,
+) -> Option<Box<dyn StorageExt>> {
+    _ = (
+        data_dir,
+        file_name,
+        format,
+        file_compression,
+        legacy_file,
+        compression_threshold,
+    );
+    None
+}
 
-        let Ok(file) = std::fs::File::create(rp).inspect_err(|e| {
-            log::error!(
-                "Failed to save application state. Could not create file {} err={e}",
-                rp.display()
-            );
-        }) else {
-            return;
-        };
+/// Returns true if the scale factor is in the range (0, 10].
+const fn is_valid_scale_factor(scale_factor: f32) -> bool {
+    scale_factor > 0.0 && scale_factor <= 10.0
+}
 
-        let mut writer = std::io::BufWriter::new(file);
-        if let Err(e) = ron::Options::default().to_io_writer_pretty(
-            &mut writer,
-            &self.kv,
-            ron::ser::PrettyConfig::default(),
-        ) {
-            log::error!(
-                "Failed to save application state. Could not write file {} err={e}",
-                rp.display()
-            );
-            return;
-        }
+/// Returns the zoom factor saved in the storage. None if there is none or it is invalid.
+fn load_zoom_factor(storage: &dyn StorageExt) -> Option<f32> {
+    let zoom_factor = storage.get_string(ZOOM_FACTOR_KEY)?.parse::<f32>().ok()?;
+    is_valid_scale_factor(zoom_factor).then_some(zoom_factor)
+}
+
+/// Restores the egui memory saved in the storage.
+#[cfg(feature = "persistence")]
+fn load_egui_memory(ctx: &Context, storage: &dyn Storage) {
+    if let Some(memory) = eframe::get_value::<egui::Memory>(storage, EGUI_MEMORY_KEY) {
+        ctx.memory_mut(|current| *current = memory);
+    }
+}
+
+/// Without the persistence feature there is no storage.
+#[cfg(not(feature = "persistence"))]
+const fn load_egui_memory(ctx: &Context, storage: &dyn Storage) {
+    _ = (ctx, storage);
+}
+
+/// Saves the egui memory in the storage.
+#[cfg(feature = "persistence")]
+fn save_egui_memory(ctx: &Context, storage: &mut dyn Storage) {
+    ctx.memory(|memory| eframe::set_value(storage, EGUI_MEMORY_KEY, memory));
+}
+
+/// Without the persistence feature there is no storage.
+#[cfg(not(feature = "persistence"))]
+const fn save_egui_memory(ctx: &Context, storage: &mut dyn Storage) {
+    _ = (ctx, storage);
+}
+
+/// Clamps the opacity to the range [0, 1]. Returns None if the window is opaque.
+fn clamp_opacity(alpha: f32) -> Option<f32> {
+    let alpha = if alpha.is_nan() {
+        1.0
+    } else {
+        alpha.clamp(0.0, 1.0)
+    };
+
+    Some(alpha).filter(|alpha| *alpha < 1.0)
+}
+
+/// Switches egui to the theme of the os. Nothing is changed if the theme cannot be detected.
+fn apply_system_theme(ctx: &Context) {
+    if let Some(theme) = detect_system_theme(ctx).and_then(SystemTheme::egui_theme) {
+        ctx.set_theme(theme);
+    }
+}
+
+/// Returns the screenshot delivered to the root viewport during the current frame.
+fn take_screenshot_event(ctx: &Context) -> Option<Arc<egui::ColorImage>> {
+    ctx.input(|i| {
+        i.raw.events.iter().rev().find_map(|event| match event {
+            egui::Event::Screenshot {
+                viewport_id, image, ..
+            } if *viewport_id == egui::ViewportId::ROOT => Some(Arc::clone(image)),
+            _ => None,
+        })
+    })
+}
+
+/// Reads the backend stored with `BackendInterop::set_preferred_backend`.
+/// Older versions always used RON with eframe, so the RON storage is checked too if another format is used.
+fn load_preferred_backend(app_name: &str, config: &BackendConfiguration) -> Option<Backend> {
+    if let Some(storage_factory) = config.storage_factory.as_ref() {
+        let value = storage_factory().get_string(PREFERRED_BACKEND_KEY)?;
+        return parse_preferred_backend(&value);
+    }
+
+    let data_dir = storage_dir(app_name, config);
+    let legacy_file = legacy_eframe_file(app_name, config);
+    //A storage file with a custom name is always in the configured format.
+    let formats = if config.storage_filename.is_some() {
+        1
+    } else {
+        2
+    };
+    let value = [config.storage_format, StorageFormat::Ron]
+        .into_iter()
+        .take(formats)
+        .filter_map(|format| {
+            open_storage(
+                data_dir.as_deref(),
+                config.storage_filename.as_deref(),
+                format,
+                config.storage_file_compression,
+                legacy_file.as_deref(),
+                None,
+            )
+        })
+        .find_map(|storage| storage.get_string(PREFERRED_BACKEND_KEY))?;
+
+    parse_preferred_backend(&value)
+}
 
-        self.dirty = false;
+/// Parses the stored backend preference. An empty value means there is no preference.
+fn parse_preferred_backend(value: &str) -> Option<Backend> {
+    if value.is_empty() {
+        return None;
     }
+
+    value
+        .parse()
+        .inspect_err(|e| log::warn!("Ignoring the stored backend preference: {e}"))
+        .ok()
 }
 
 /// Run the app using the selected backend.
@@ -412,8 +3262,35 @@ impl Storage for KVStorage {
 ///
 /// # Errors
 /// * If this function is not called in the main thread.
-/// * If this function is called more than once.
+/// * If this function is called while the app is running.
 /// * If `eframe` or the `egui_software_backend` fails.
+/// * If this function is called again after the event loop exited and the backend cannot create another event loop.
+///
+/// # Relaunching
+/// Once the event loop exited, this function can be called again to show a new window.
+/// The same backend is used again unless `BackendConfiguration::with_reselect_on_relaunch` is enabled.
+/// eframe only returns from its event loop if `NativeOptions::run_and_return` is enabled, which is the default.
+/// eframe keeps its event loop for the next call, but winit does not allow creating a second event loop in a process.
+/// The software backend always creates a new event loop, so it cannot run after any backend ran before,
+/// and eframe cannot run after the software backend. Only eframe can be relaunched after eframe.
+/// All other combinations fail with `BackendSelectorError::RelaunchUnsupported`.
+///
+/// # Precedence
+/// The backend is selected in this order, the first one that applies wins:
+/// 1. The software backend if the process was relaunched because eframe failed to initialize.
+/// 2. The `--egui-backend` command line argument if `BackendConfiguration::with_parse_cli_args` is enabled.
+/// 3. The backend set with `overwrite_backend`.
+/// 4. The stored preference if `BackendConfiguration::with_persist_backend_choice` is enabled.
+/// 5. The function set with `BackendConfiguration::with_backend_override_fn`.
+/// 6. The first backend registered with `BackendConfiguration::register_custom_backend`.
+/// 7. The probes registered with `register_probe`.
+/// 8. The built-in detection.
+///
+/// Crash protection and `BackendConfiguration::with_allowed_backends` are applied on top of that.
+///
+/// # Storage
+/// The app factory receives the same storage on both backends, so the data saved by `App::save` is always available to it.
+/// The storage is None if the `persistence` feature is disabled or the storage directory cannot be determined.
 ///
 /// # Example
 /// ```rust
@@ -446,175 +3323,615 @@ pub fn run_app<T: App>(
     app_name: &str,
     backend_configuration: impl Into<BackendConfiguration>,
     mut app_factory: impl FnMut(Context, Option<&dyn Storage>) -> T,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), BackendSelectorError> {
+    try_run_app(app_name, backend_configuration, move |ctx, storage| {
+        Ok(app_factory(ctx, storage))
+    })
+}
+
+/// Run the app using the selected backend with a fallible app factory.
+/// This function behaves exactly like `run_app`, except that the app factory may fail.
+///
+/// # Errors
+/// * If this function is not called in the main thread.
+/// * If this function is called while the app is running.
+/// * If the app factory fails. This is reported as `BackendSelectorError::AppCreation`.
+/// * If `eframe` or the `egui_software_backend` fails.
+/// * If this function is called again after the event loop exited and the backend cannot create another event loop.
+///
+pub fn try_run_app<T: App>(
+    app_name: &str,
+    backend_configuration: impl Into<BackendConfiguration>,
+    app_factory: impl FnMut(Context, Option<&dyn Storage>) -> Result<T, Box<dyn Error + Send + Sync>>,
+) -> Result<(), BackendSelectorError> {
     if IsMainThread::OtherThread == main_thread::is_main_thread() {
-        return Err("Current thread is not the main thread".into());
+        return Err(BackendSelectorError::NotMainThread);
     }
 
+    handle_probe_subprocess();
+
     if is_launched() {
-        return Err("Application already launched".into());
+        return Err(BackendSelectorError::AlreadyLaunched);
     }
 
-    let config = backend_configuration.into();
-    match get_backend() {
-        None | Some(Backend::SoftwareBackend) => {
-            STATE.store(3, Relaxed);
-            let mut cfg_to_use = config.software_backend_options.unwrap_or_default();
-            cfg_to_use.viewport_builder = config.viewport;
+    let config: BackendConfiguration = backend_configuration.into();
 
-            let app_name = app_name.to_string();
+    if let Some(scale_factor) = config.scale_factor
+        && !is_valid_scale_factor(scale_factor)
+    {
+        return Err(BackendSelectorError::InvalidScaleFactor(scale_factor));
+    }
+
+    //A relaunched process runs while the original process still holds the lock.
+    #[cfg(feature = "single-instance")]
+    let _instance_lock = if config.single_instance && !is_fallback_process(&config) {
+        match crate::single_instance::acquire(app_name) {
+            InstanceLockState::Acquired(lock) => Some(lock),
+            InstanceLockState::AlreadyRunning => return Err(BackendSelectorError::AlreadyRunning),
+            InstanceLockState::NotEnforced => None,
+        }
+    } else {
+        None
+    };
 
-            if let Err(e) =
-                egui_software_backend::run_app_with_software_backend(cfg_to_use, move |ctx| {
-                    #[cfg(feature = "persistence")]
-                    let storage: Option<Box<dyn Storage>> =
-                        KVStorage::new(&app_name).map(|a| Box::new(a) as Box<dyn Storage>);
+    let selected_backend = select_launch_backend(app_name, &config)?;
+
+    //The configuration is moved into the backend, so the directory of the marker is resolved before.
+    #[cfg(feature = "persistence")]
+    let crash_marker_dir = config
+        .crash_protection
+        .then(|| storage_dir(app_name, &config))
+        .flatten();
+    let reselect_on_relaunch = config.reselect_on_relaunch;
+    let relaunch = EVENT_LOOP_EXITED.load(Relaxed);
+    let result = match selected_backend {
+        Backend::SoftwareBackend => launch_software_backend(app_name, config, app_factory),
+        Backend::Eframe => launch_eframe(app_name, config, app_factory),
+        Backend::CustomBackend(id) => config.take_custom_backend(id).map_or_else(
+            || Err(BackendSelectorError::BackendUnavailable(selected_backend)),
+            |launch_fn| {
+                STATE.store(launched_state(selected_backend), Relaxed);
+                let result = launch_fn(config).map_err(BackendSelectorError::CustomBackend);
+                EVENT_LOOP_EXITED.store(true, Relaxed);
+                result
+            },
+        ),
+    };
 
-                    #[cfg(not(feature = "persistence"))]
-                    let storage: Option<Box<dyn Storage>> = None;
+    #[cfg(feature = "persistence")]
+    if result.is_ok()
+        && let Some(crash_marker_dir) = crash_marker_dir
+    {
+        crate::crash_marker::clear(&crash_marker_dir);
+    }
 
-                    #[cfg(not(feature = "persistence"))]
-                    let _ignored = &app_name;
+    //run_app may be called again once the launch returned.
+    let launched = state_backend(STATE.load(Relaxed));
+    STATE.store(
+        if reselect_on_relaunch {
+            0
+        } else {
+            launched.map_or(0, selected_state)
+        },
+        Relaxed,
+    );
+
+    match result {
+        Err(e @ (BackendSelectorError::Eframe(_) | BackendSelectorError::SoftwareBackend(_)))
+            if relaunch =>
+        {
+            //The most likely cause is that the backend could not create another event loop.
+            Err(BackendSelectorError::RelaunchUnsupported(Box::new(e)))
+        }
+        result => result,
+    }
+}
 
-                    let integration_info = IntegrationInfo { cpu_usage: None };
+/// Selects the backend `try_run_app` launches and calls the callback of `BackendConfiguration::on_backend_selected`.
+fn select_launch_backend(
+    app_name: &str,
+    config: &BackendConfiguration,
+) -> Result<Backend, BackendSelectorError> {
+    if config.persist_backend_choice
+        && STATE.load(Relaxed) == 0
+        && let Some(backend) = load_preferred_backend(app_name, config)
+    {
+        overwrite_backend_with_report(report(
+            backend,
+            BackendSelectionReason::StoredPreference,
+            String::new(),
+        ))?;
+    }
 
-                    AppWrapper(
-                        app_factory(ctx, storage.as_ref().map(Box::as_ref)),
-                        storage,
-                        integration_info,
-                    )
-                })
-            {
-                return Err(Box::new(e));
-            }
+    if config.parse_cli_args
+        && let Some(backend) = backend_from_args(
+            std::env::args_os()
+                .skip(1)
+                .filter_map(|arg| arg.into_string().ok()),
+        )
+    {
+        overwrite_backend_with_report(report(
+            backend,
+            BackendSelectionReason::CommandLine,
+            format!("--egui-backend={backend}"),
+        ))?;
+    }
 
-            Ok(())
-        }
-        Some(Backend::Eframe) => {
-            STATE.store(4, Relaxed);
-            let mut cfg_to_use = config.eframe_options.unwrap_or_default();
-            cfg_to_use.viewport = config.viewport;
-
-            let integration_info = IntegrationInfo { cpu_usage: None };
-
-            if let Err(e) = eframe::run_native(
-                app_name,
-                cfg_to_use,
-                Box::new(move |ctx| {
-                    Ok(Box::new(AppWrapper(
-                        app_factory(ctx.egui_ctx.clone(), ctx.storage),
-                        None,
-                        integration_info,
-                    )))
-                }),
-            ) {
-                return Err(Box::new(e));
-            }
+    if is_fallback_process(config) {
+        //This process was relaunched because eframe failed to initialize.
+        overwrite_backend_with_report(report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::EframeFailed,
+            String::new(),
+        ))?;
+    }
 
-            Ok(())
-        }
+    //The relaunched process of the eframe fallback is an explicit choice too,
+    //its parent left the marker of the failed eframe run behind.
+    #[cfg(feature = "persistence")]
+    if config.crash_protection
+        && !is_explicit_selection()
+        && storage_dir(app_name, config)
+            .and_then(|storage_dir| crate::crash_marker::read(&storage_dir))
+            == Some(Backend::Eframe)
+    {
+        log::warn!(
+            "The previous run with eframe did not exit cleanly, using the software backend instead."
+        );
+        overwrite_backend_with_report(report(
+            Backend::SoftwareBackend,
+            BackendSelectionReason::PreviousRunCrashed,
+            String::new(),
+        ))?;
     }
-}
 
-/// Choose backend on Not windows and not linux. (Basically choose eframe everytime)
-#[cfg(all(not(windows), not(target_os = "linux")))]
-fn determine_backend() -> Option<Backend> {
-    //macOS and BSD.
-    Some(Backend::Eframe)
-}
+    if let Some(backend_override_fn) = config.backend_override_fn
+        && STATE.load(Acquire) == 0
+        && let Some(backend) = backend_override_fn()
+    {
+        overwrite_backend_with_report(report(
+            backend,
+            BackendSelectionReason::CustomProbe,
+            "backend override fn",
+        ))?;
+    }
 
-/// Linux-specific code to decide which backend to use
-#[cfg(target_os = "linux")]
-#[allow(clippy::unnecessary_wraps)]
-fn determine_backend() -> Option<Backend> {
-    //We only care about remote display sessions here, because eframe performs poorly on those.
+    if let Some((id, _)) = config.custom_backends.first()
+        && STATE.load(Acquire) == 0
+    {
+        overwrite_backend_with_report(report(
+            Backend::CustomBackend(*id),
+            BackendSelectionReason::CustomBackend,
+            String::new(),
+        ))?;
+    }
 
-    let Ok(display) = std::env::var("DISPLAY") else {
-        //We are not on X11, must be wayland where eframe works.
-        //I don't have any experience with waypipe (wayland via ssh) TODO test this?
-        return Some(Backend::Eframe);
+    let detected_backend = select_backend(&config.detection).unwrap_or(Backend::SoftwareBackend);
+    let Some(selected_backend) = config.allowed_backend(detected_backend) else {
+        return Err(BackendSelectorError::NoAllowedBackend);
     };
 
-    if !display.starts_with(':') && !display.contains("/unix:") {
-        //This is remote X11 session. OpenGL will be the slowest thing in the universe.
-        return Some(Backend::SoftwareBackend);
+    if selected_backend != detected_backend {
+        log::info!("{detected_backend:?} is not allowed, using {selected_backend:?} instead.");
+        overwrite_backend_with_report(report(
+            selected_backend,
+            BackendSelectionReason::NotAllowed,
+            format!("{detected_backend:?} is not allowed"),
+        ))?;
+    }
+
+    #[cfg(feature = "persistence")]
+    if config.crash_protection
+        && let Some(storage_dir) = storage_dir(app_name, config)
+    {
+        crate::crash_marker::write(&storage_dir, selected_backend);
+    }
+
+    if let Some(callback) = config
+        .backend_selected_callback
+        .as_ref()
+        .and_then(|callback| {
+            callback
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .take()
+        })
+    {
+        callback(selected_backend);
     }
 
-    //We could check if opengl is present, however nearly all linux distros nowadays come with at least mesa llvm-pipe.
-    //TODO think about this.
+    Ok(selected_backend)
+}
 
-    Some(Backend::Eframe)
+/// Returns true if this process was relaunched because eframe failed to initialize.
+/// The environment is queried through the platform detector of the configuration, so the relaunch can be faked.
+fn is_fallback_process(config: &BackendConfiguration) -> bool {
+    config
+        .detection
+        .platform_detector
+        .env_var(FALLBACK_ENV_VAR)
+        .is_some()
 }
 
-/// Windows-specific code to determine which backend to use.
-#[cfg(windows)]
-fn determine_backend() -> Option<Backend> {
-    if IsMainThread::OtherThread == main_thread::is_main_thread() {
-        return None;
+/// Runs the app with the software backend until its window is closed.
+fn launch_software_backend<T: App>(
+    app_name: &str,
+    mut config: BackendConfiguration,
+    mut app_factory: impl FnMut(
+        Context,
+        Option<&dyn Storage>,
+    ) -> Result<T, Box<dyn Error + Send + Sync>>,
+) -> Result<(), BackendSelectorError> {
+    STATE.store(launched_state(Backend::SoftwareBackend), Relaxed);
+    //The options are taken out, the remaining configuration is still needed below.
+    let mut cfg_to_use = config.software_backend_options.take().unwrap_or_default();
+    cfg_to_use.viewport_builder = std::mem::take(&mut config.viewport);
+
+    let mut data_dir = storage_dir(app_name, &config).filter(|_| config.storage_factory.is_none());
+    let legacy_file = legacy_eframe_file(app_name, &config);
+    let mut storage = open_app_storage(&config, data_dir.as_deref(), legacy_file.as_deref());
+    if let Some(storage) = storage.as_deref_mut() {
+        run_migrations(storage, &config.storage_migrations);
+    }
+
+    let mut options = AppWrapperOptions::new(&config);
+    if config.persist_window_geometry
+        && let Some(storage) = storage.as_deref()
+        && let Some(geometry) = WindowGeometry::load(storage)
+    {
+        geometry.apply(&mut cfg_to_use.viewport_builder);
+        options.window_geometry = Some(geometry);
+    }
+    let scale_factor = config.scale_factor;
+    let system_theme = config.system_theme;
+    if config.accessibility {
+        log::warn!("The software backend does not support accessibility");
+    }
+
+    //The factory runs inside the event loop, so the error has to be sent out of it.
+    let (error_sender, error_receiver) = std::sync::mpsc::channel();
+
+    let run_result = egui_software_backend::run_app_with_software_backend(cfg_to_use, move |ctx| {
+        let storage = storage.take();
+        if let Some(scale_factor) = scale_factor {
+            ctx.set_pixels_per_point(scale_factor);
+        }
+
+        if system_theme {
+            apply_system_theme(&ctx);
+        }
+
+        let wrapper_ctx = ctx.clone();
+        wrapper_ctx.add_plugin(ScreenshotPlugin::default());
+
+        match app_factory(
+            ctx,
+            storage
+                .as_ref()
+                .map(|storage| storage.as_ref() as &dyn Storage),
+        ) {
+            Ok(app) => SoftwareBackendApp::Created(Box::new(AppWrapper::new(
+                app,
+                storage,
+                data_dir.take(),
+                options,
+                &wrapper_ctx,
+            ))),
+            Err(e) => {
+                _ = error_sender.send(e);
+                SoftwareBackendApp::Failed
+            }
+        }
+    });
+    EVENT_LOOP_EXITED.store(true, Relaxed);
+
+    match run_result {
+        Err(e) => Err(BackendSelectorError::SoftwareBackend(Box::new(e))),
+        Ok(()) => error_receiver
+            .try_recv()
+            .map_or(Ok(()), |e| Err(BackendSelectorError::AppCreation(e))),
+    }
+}
+
+/// Runs the app with eframe until its window is closed.
+/// Relaunches the process with the software backend if eframe fails to initialize and the fallback is enabled.
+fn launch_eframe<T: App>(
+    app_name: &str,
+    mut config: BackendConfiguration,
+    mut app_factory: impl FnMut(
+        Context,
+        Option<&dyn Storage>,
+    ) -> Result<T, Box<dyn Error + Send + Sync>>,
+) -> Result<(), BackendSelectorError> {
+    STATE.store(launched_state(Backend::Eframe), Relaxed);
+    let mut cfg_to_use = config.eframe_options.take().unwrap_or_default();
+    cfg_to_use.viewport = std::mem::take(&mut config.viewport);
+    if let Some(vsync) = config.vsync {
+        cfg_to_use.vsync = vsync;
+    }
+
+    //The app uses the same storage as with the software backend,
+    //eframe only keeps its own state, like the window state, in a separate file.
+    let data_dir = storage_dir(app_name, &config);
+    let legacy_file = legacy_eframe_file(app_name, &config);
+
+    #[cfg(feature = "persistence")]
+    if cfg_to_use.persistence_path.is_none()
+        && let Some(data_dir) = data_dir.as_ref()
+    {
+        let eframe_state_file = data_dir.join(EFRAME_STATE_FILE_NAME);
+        if let Some(legacy_file) = legacy_file.as_deref() {
+            import_eframe_state(legacy_file, &eframe_state_file);
+        }
+
+        cfg_to_use.persistence_path = Some(eframe_state_file);
     }
 
-    if unsafe {
-        windows_sys::Win32::UI::WindowsAndMessaging::GetSystemMetrics(
-            windows_sys::Win32::UI::WindowsAndMessaging::SM_REMOTESESSION,
-        ) != 0
-    } {
-        //Technically, we could query some obscure registry keys here,
-        //as well as some group policies. It is technically possible to enable opengl 3.2 via RDP,
-        //however, it is so poorly documented by microsoft that I only managed to do it once by accident and could never reproduce it.
-        //Needless to say, if it's an RDP connection, then we just use the software renderer.
-        return Some(Backend::SoftwareBackend);
+    let mut storage = open_app_storage(&config, data_dir.as_deref(), legacy_file.as_deref());
+    if let Some(storage) = storage.as_deref_mut() {
+        run_migrations(storage, &config.storage_migrations);
     }
 
-    //We dont need to check this on aarch64 as I am pretty sure that only KVM supports this properly and the virtio drivers for it actually work with eframe.
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86"))]
+    let mut options = AppWrapperOptions::new(&config);
+    if config.persist_window_geometry
+        && let Some(storage) = storage.as_deref()
+        && let Some(geometry) = WindowGeometry::load(storage)
     {
-        if raw_cpuid::CpuId::new().get_hypervisor_info().is_some() {
-            //We are running on a VM.
-
-            // These checks cover sensible use cases.
-            // I.e., They assume that it's unlikely someone migrated a VM from VirtualBox to KVM,
-            // without first uninstalling all the VirtualBox drivers.
-            // We could list the actual device drivers, but windows decided to remove that
-            // Windows api function because of "security" with Windows 11 24H2.
-            // I don't want to parse the stdout output of "querydriver.exe" yet.
-
-            //Process will segfault if we try eframe. This is the VMWare 3d driver. It's not good enough.
-            if std::fs::exists("C:\\Windows\\System32\\vm3dgl64.dll").unwrap_or(false) {
-                return Some(Backend::SoftwareBackend);
+        geometry.apply(&mut cfg_to_use.viewport);
+        options.window_geometry = Some(geometry);
+    }
+    options.vsync = cfg_to_use.vsync;
+
+    //The directory only holds the state of eframe if the app storage was set with with_storage.
+    let data_dir = data_dir.filter(|_| config.storage_factory.is_none());
+
+    let scale_factor = config.scale_factor;
+    let system_theme = config.system_theme;
+    let accessibility = config.accessibility;
+    #[cfg(not(feature = "accessibility"))]
+    if accessibility {
+        log::warn!("Accessibility requires the accessibility feature of egui_backend_selector");
+    }
+
+    let run_result = eframe::run_native(
+        app_name,
+        cfg_to_use,
+        Box::new(move |ctx| {
+            if let Some(scale_factor) = scale_factor {
+                ctx.egui_ctx.set_pixels_per_point(scale_factor);
+            }
+
+            if system_theme {
+                apply_system_theme(&ctx.egui_ctx);
             }
 
-            //Eframe will fail to launch due to missing gl extensions. This is the Virtualbox opengl driver.
-            if std::fs::exists("C:\\Windows\\System32\\VBoxGL.dll").unwrap_or(false) {
-                return Some(Backend::SoftwareBackend);
+            #[cfg(feature = "accessibility")]
+            if accessibility {
+                ctx.egui_ctx.enable_accesskit();
             }
 
-            //TODO test if it works with Hyper-V? (I never tested if eframe works on there by default)
+            let app = app_factory(
+                ctx.egui_ctx.clone(),
+                storage
+                    .as_ref()
+                    .map(|storage| storage.as_ref() as &dyn Storage),
+            )?;
+            Ok(Box::new(AppWrapper::new(
+                app,
+                storage,
+                data_dir,
+                options,
+                &ctx.egui_ctx,
+            )))
+        }),
+    );
+    EVENT_LOOP_EXITED.store(true, Relaxed);
+
+    eframe_run_result(
+        run_result,
+        config.eframe_fallback,
+        relaunch_with_software_backend,
+    )
+}
+
+/// Maps the result of `eframe::run_native`, graphics initialization errors are passed to `fallback` if enabled.
+fn eframe_run_result(
+    run_result: Result<(), eframe::Error>,
+    eframe_fallback: bool,
+    fallback: impl FnOnce(eframe::Error) -> Result<(), BackendSelectorError>,
+) -> Result<(), BackendSelectorError> {
+    match run_result {
+        Ok(()) => Ok(()),
+        Err(eframe::Error::AppCreation(e)) => Err(BackendSelectorError::AppCreation(e)),
+        Err(e) if eframe_fallback && is_graphics_initialization_error(&e) => fallback(e),
+        Err(e) => Err(BackendSelectorError::Eframe(e)),
+    }
+}
+
+/// Returns true if the eframe error indicates that opengl or wgpu could not be initialized.
+const fn is_graphics_initialization_error(error: &eframe::Error) -> bool {
+    match error {
+        #[cfg(feature = "glow")]
+        eframe::Error::Glutin(_)
+        | eframe::Error::NoGlutinConfigs(..)
+        | eframe::Error::OpenGL(_) => true,
+        #[cfg(feature = "wgpu")]
+        eframe::Error::Wgpu(_) => true,
+        _ => false,
+    }
+}
 
-            //Probably KVM? if so theres a high chance that eframe works.
+/// Relaunches the current executable with the software backend forced, because eframe failed to initialize.
+fn relaunch_with_software_backend(eframe_error: eframe::Error) -> Result<(), BackendSelectorError> {
+    log::error!(
+        "eframe failed to initialize, relaunching with the software backend. err={eframe_error}"
+    );
+
+    STATE.store(launched_state(Backend::SoftwareBackend), Relaxed);
+
+    let status = std::env::current_exe().and_then(|exe| {
+        std::process::Command::new(exe)
+            .args(std::env::args_os().skip(1))
+            .env(FALLBACK_ENV_VAR, "1")
+            .status()
+    });
+
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(BackendSelectorError::FallbackFailed(status)),
+        Err(e) => {
+            log::error!("Failed to relaunch with the software backend. err={e}");
+            Err(BackendSelectorError::Eframe(eframe_error))
         }
     }
+}
 
-    let Ok(mut glfw) = glfw::init::<()>(None) else {
-        //No opengl at all, this is some virgin post-installer windows with no drivers.
-        return Some(Backend::SoftwareBackend);
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform_detector::FakePlatformDetector;
+    use crate::report::selection_report;
+    use std::sync::MutexGuard;
+
+    /// Serializes the tests that change the global state.
+    static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Locks the global state and resets it to no selected backend.
+    fn lock_state() -> MutexGuard<'static, ()> {
+        let guard = STATE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        STATE.store(0, Relaxed);
+        EVENT_LOOP_EXITED.store(false, Relaxed);
+        guard
+    }
 
-    //The minimum version for eframe to work appears to be opengl 3.2
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 2));
-    glfw.window_hint(glfw::WindowHint::Visible(false));
-
-    let Some((wnd, events)) = glfw.create_window(
-        128,
-        128,
-        "opengl version detector",
-        glfw::WindowMode::Windowed,
-    ) else {
-        //Opengl is too old. This is a catch-all for "other" hypervisors with insufficient opengl implementations.
-        return Some(Backend::SoftwareBackend);
-    };
+    /// Returns a configuration that only selects the backend with the given detector.
+    fn configuration(detector: FakePlatformDetector) -> BackendConfiguration {
+        BackendConfiguration::default()
+            .with_platform_detector(Box::new(detector))
+            .with_crash_protection(false)
+            .with_persist_backend_choice(false)
+            .with_parse_cli_args(false)
+    }
+
+    /// Graphics initialization errors are passed to the fallback, all other errors are returned.
+    #[test]
+    fn eframe_fallback_only_handles_graphics_errors() {
+        let app_error = || Err(eframe::Error::AppCreation("app error".into()));
+        let result = eframe_run_result(app_error(), true, |_| panic!("fallback for an app error"));
+        assert!(matches!(result, Err(BackendSelectorError::AppCreation(_))));
+
+        #[cfg(feature = "glow")]
+        {
+            let gl_error =
+                || Err(eframe::egui_glow::PainterError::from("no context".to_owned()).into());
+
+            let mut fallback_called = false;
+            let result = eframe_run_result(gl_error(), true, |e| {
+                assert!(matches!(e, eframe::Error::OpenGL(_)));
+                fallback_called = true;
+                Ok(())
+            });
+            assert!(result.is_ok());
+            assert!(fallback_called);
+
+            let result = eframe_run_result(gl_error(), false, |_| panic!("fallback is disabled"));
+            assert!(matches!(
+                result,
+                Err(BackendSelectorError::Eframe(eframe::Error::OpenGL(_)))
+            ));
+        }
+    }
 
-    drop(events);
-    drop(wnd);
+    /// The relaunched process uses the software backend, even though eframe would be detected.
+    #[test]
+    fn fallback_process_selects_software_backend() {
+        let _guard = lock_state();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let config =
+            configuration(FakePlatformDetector::linux_desktop().with_env(FALLBACK_ENV_VAR, "1"))
+                .on_backend_selected(move |backend| {
+                    _ = sender.send(backend);
+                });
+
+        assert!(is_fallback_process(&config));
+        let backend = select_launch_backend("fallback_process_test", &config)
+            .expect("backend selection failed");
+
+        assert_eq!(backend, Backend::SoftwareBackend);
+        assert_eq!(
+            selection_report().map(|report| report.reason),
+            Some(BackendSelectionReason::EframeFailed)
+        );
+        assert_eq!(receiver.try_recv().ok(), Some(Backend::SoftwareBackend));
+        STATE.store(0, Relaxed);
+    }
+
+    /// The callback is called once with the overwritten backend, before the backend is launched.
+    #[test]
+    fn backend_selected_callback_runs_once_before_launch() {
+        let _guard = lock_state();
+        overwrite_backend(Backend::SoftwareBackend).expect("overwrite failed");
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let config = configuration(FakePlatformDetector::linux_desktop()).on_backend_selected(
+            move |backend| {
+                _ = sender.send((backend, is_launched()));
+            },
+        );
+
+        for _ in 0..2 {
+            let backend =
+                select_launch_backend("callback_test", &config).expect("backend selection failed");
+            assert_eq!(backend, Backend::SoftwareBackend);
+        }
+
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            [(Backend::SoftwareBackend, false)]
+        );
+        STATE.store(0, Relaxed);
+    }
+
+    /// An overwrite that returned Ok is never lost to a detection that runs concurrently.
+    #[test]
+    fn overwrite_wins_against_concurrent_detection() {
+        let _guard = lock_state();
+        let options = DetectionOptions {
+            platform_detector: Arc::new(FakePlatformDetector::linux_desktop()),
+            ..DetectionOptions::default()
+        };
+
+        for _ in 0..200 {
+            STATE.store(0, Relaxed);
+            let barrier = std::sync::Barrier::new(3);
+            std::thread::scope(|scope| {
+                for _ in 0..2 {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        select_backend(&options).expect("detection failed");
+                    });
+                }
+                scope.spawn(|| {
+                    barrier.wait();
+                    overwrite_backend(Backend::SoftwareBackend).expect("overwrite failed");
+                });
+            });
+
+            assert_eq!(
+                state_backend(STATE.load(Acquire)),
+                Some(Backend::SoftwareBackend)
+            );
+            assert_eq!(select_backend(&options), Some(Backend::SoftwareBackend));
+        }
 
-    Some(Backend::Eframe)
+        STATE.store(launched_state(Backend::Eframe), Relaxed);
+        assert!(is_launched());
+        assert!(matches!(
+            overwrite_backend(Backend::SoftwareBackend),
+            Err(OverwriteError::AlreadyLaunched)
+        ));
+        assert_eq!(select_backend(&options), Some(Backend::Eframe));
+        STATE.store(0, Relaxed);
+    }
 }
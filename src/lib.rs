@@ -50,9 +50,98 @@
 #[cfg(not(target_arch = "wasm32"))]
 mod implementation;
 
+/// The implementation for the web is found in this file.
+#[cfg(target_arch = "wasm32")]
+mod wasm_implementation;
+
+/// The error type is found in this file.
+mod error;
+
+/// The cache for the result of the opengl probe is found in this file.
+#[cfg(windows)]
+mod probe_cache;
+
+/// The macOS system queries used by the detection are found in this file.
+#[cfg(target_os = "macos")]
+mod macos;
+
+/// The platform-specific detection of the backend is found in this file.
+#[cfg(not(target_arch = "wasm32"))]
+mod detection;
+
+/// The access to the system used by the detection is found in this file.
+mod platform_detector;
+
+/// Queries about the platform the built-in detection is based on.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod platform;
+
+/// The validation of window icons is found in this file.
+mod icon;
+
+/// The information about monitors is found in this file.
+mod monitor;
+
+/// The frame timing statistics are found in this file.
+mod frame_stats;
+
+/// The detection of the theme of the os is found in this file.
+mod system_theme;
+
+/// The capabilities of the backends are found in this file.
+mod capabilities;
+
+/// The command line argument handling is found in this file.
+mod cli;
+
+/// The report of why a backend was selected is found in this file.
+mod report;
+
+/// The storage is found in this file.
+mod storage;
+
+/// The lock that prevents a second instance of the app is found in this file.
+#[cfg(all(not(target_arch = "wasm32"), feature = "single-instance"))]
+mod single_instance;
+
+/// The screenshots of the software backend are found in this file.
+#[cfg(not(target_arch = "wasm32"))]
+mod software_screenshot;
+
+/// The marker that detects crashes of the previous run is found in this file.
+#[cfg(all(not(target_arch = "wasm32"), feature = "persistence"))]
+mod crash_marker;
+
 #[cfg(not(target_arch = "wasm32"))]
 pub use implementation::*;
 
+#[cfg(target_arch = "wasm32")]
+pub use wasm_implementation::*;
+
+pub use error::*;
+
+pub use storage::{MemoryStorage, StorageCompression, StorageExt, StorageFormat, StorageMigration};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use detection::{handle_probe_subprocess, probe_backends, register_probe};
+
+pub use cli::backend_from_args;
+
+pub use platform_detector::{PlatformDetector, RealPlatformDetector};
+
+pub use monitor::MonitorInfo;
+
+pub use capabilities::BackendCapabilities;
+
+pub use system_theme::SystemTheme;
+
+pub use frame_stats::FrameStats;
+
+pub use report::{
+    BackendProbeResult, BackendSelectionReason, BackendSelectionReport,
+    get_backend_selection_reason, selection_report,
+};
+
 #[cfg(doctest)]
 #[doc = include_str!("../README.md")]
 struct ReadmeDocTests;
@@ -0,0 +1,206 @@
+use std::ffi::{CStr, c_char, c_int, c_void};
+
+/// `kCFStringEncodingUTF8` from the CoreFoundation headers.
+const CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+/// `kCGLPFANoRecovery` from the CGL headers.
+const CGL_PFA_NO_RECOVERY: c_int = 72;
+
+/// `kCGLPFAAccelerated` from the CGL headers.
+const CGL_PFA_ACCELERATED: c_int = 73;
+
+/// `kCGLPFAOpenGLProfile` from the CGL headers.
+const CGL_PFA_OPENGL_PROFILE: c_int = 99;
+
+/// `kCGLOGLPVersion_3_2_Core` from the CGL headers.
+const CGL_OGLP_VERSION_3_2_CORE: c_int = 0x3200;
+
+/// `kCGLOGLPVersion_GL4_Core` from the CGL headers.
+const CGL_OGLP_VERSION_4_1_CORE: c_int = 0x4100;
+
+unsafe extern "C" {
+    /// Reads a sysctl value by its name.
+    fn sysctlbyname(
+        name: *const c_char,
+        oldp: *mut c_void,
+        oldlenp: *mut usize,
+        newp: *mut c_void,
+        newlen: usize,
+    ) -> c_int;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+unsafe extern "C" {
+    /// Creates a `CFString` from a nul terminated string.
+    fn CFStringCreateWithCString(
+        alloc: *const c_void,
+        c_str: *const c_char,
+        encoding: u32,
+    ) -> *const c_void;
+
+    /// Returns the value of the key or null if the dictionary does not contain the key.
+    fn CFDictionaryGetValue(dict: *const c_void, key: *const c_void) -> *const c_void;
+
+    /// Returns the type of the object.
+    fn CFGetTypeID(cf: *const c_void) -> usize;
+
+    /// Returns the type of `CFBoolean`.
+    fn CFBooleanGetTypeID() -> usize;
+
+    /// Returns the value of a `CFBoolean`.
+    fn CFBooleanGetValue(boolean: *const c_void) -> u8;
+
+    /// Releases an object that was created or copied.
+    fn CFRelease(cf: *const c_void);
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+unsafe extern "C" {
+    /// Returns the dictionary describing the login session of the current process, or null if there is none.
+    fn CGSessionCopyCurrentDictionary() -> *const c_void;
+}
+
+#[link(name = "OpenGL", kind = "framework")]
+unsafe extern "C" {
+    /// Returns the pixel formats matching the attributes.
+    fn CGLChoosePixelFormat(
+        attribs: *const c_int,
+        pix: *mut *mut c_void,
+        npix: *mut c_int,
+    ) -> c_int;
+
+    /// Frees a pixel format returned by `CGLChoosePixelFormat`.
+    fn CGLDestroyPixelFormat(pix: *mut c_void) -> c_int;
+}
+
+/// Reads an integer sysctl value. Returns None if the sysctl does not exist.
+fn sysctl_int(name: &CStr) -> Option<i32> {
+    let mut value: i32 = 0;
+    let mut size = size_of::<i32>();
+    let result = unsafe {
+        sysctlbyname(
+            name.as_ptr(),
+            (&raw mut value).cast(),
+            &raw mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+
+    (result == 0).then_some(value)
+}
+
+/// Reads a string sysctl value. Returns None if the sysctl does not exist.
+#[cfg(target_arch = "x86_64")]
+fn sysctl_string(name: &CStr) -> Option<String> {
+    let mut buffer = [0u8; 4096];
+    let mut size = buffer.len();
+    let result = unsafe {
+        sysctlbyname(
+            name.as_ptr(),
+            buffer.as_mut_ptr().cast(),
+            &raw mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+
+    if result != 0 {
+        return None;
+    }
+
+    let value = CStr::from_bytes_until_nul(&buffer[..size.min(buffer.len())]).ok()?;
+    Some(value.to_string_lossy().into_owned())
+}
+
+/// Returns true if the system runs in a virtual machine.
+pub fn is_virtual_machine() -> bool {
+    if sysctl_int(c"kern.hv_vmm_present") == Some(1) {
+        return true;
+    }
+
+    //Older intel macOS versions do not have kern.hv_vmm_present, but report the hypervisor cpu feature.
+    #[cfg(target_arch = "x86_64")]
+    if sysctl_string(c"machdep.cpu.features")
+        .is_some_and(|features| features.split_whitespace().any(|feature| feature == "VMM"))
+    {
+        return true;
+    }
+
+    false
+}
+
+/// Returns Some(false) if the login session of the current process is not the one on the physical console,
+/// which is the case for remote sessions started with Screen Sharing or Apple Remote Desktop.
+/// Returns None if the session could not be queried.
+pub fn is_on_console() -> Option<bool> {
+    let session = unsafe { CGSessionCopyCurrentDictionary() };
+    if session.is_null() {
+        return None;
+    }
+
+    let key = unsafe {
+        CFStringCreateWithCString(
+            std::ptr::null(),
+            c"kCGSessionOnConsoleKey".as_ptr(),
+            CF_STRING_ENCODING_UTF8,
+        )
+    };
+
+    let on_console = if key.is_null() {
+        None
+    } else {
+        let value = unsafe { CFDictionaryGetValue(session, key) };
+        let on_console = (!value.is_null()
+            && unsafe { CFGetTypeID(value) == CFBooleanGetTypeID() })
+        .then(|| unsafe { CFBooleanGetValue(value) } != 0);
+        unsafe { CFRelease(key) };
+        on_console
+    };
+
+    unsafe { CFRelease(session) };
+    on_console
+}
+
+/// Returns true if there is a hardware accelerated opengl 3.2 pixel format.
+/// No context is created to check this.
+pub fn has_accelerated_gl() -> bool {
+    has_accelerated_gl_profile(CGL_OGLP_VERSION_3_2_CORE)
+}
+
+/// Returns whether there is a hardware accelerated pixel format for at least the opengl version.
+/// macOS only provides profiles up to opengl 4.1, None for newer versions.
+pub fn has_accelerated_gl_version(major: u8, minor: u8) -> Option<bool> {
+    let profile = if (major, minor) <= (3, 2) {
+        CGL_OGLP_VERSION_3_2_CORE
+    } else if (major, minor) <= (4, 1) {
+        CGL_OGLP_VERSION_4_1_CORE
+    } else {
+        return None;
+    };
+
+    Some(has_accelerated_gl_profile(profile))
+}
+
+/// Returns true if there is a hardware accelerated pixel format for the opengl profile.
+fn has_accelerated_gl_profile(profile: c_int) -> bool {
+    let attributes = [
+        CGL_PFA_ACCELERATED,
+        CGL_PFA_NO_RECOVERY,
+        CGL_PFA_OPENGL_PROFILE,
+        profile,
+        0,
+    ];
+
+    let mut pixel_format = std::ptr::null_mut();
+    let mut count: c_int = 0;
+    let result =
+        unsafe { CGLChoosePixelFormat(attributes.as_ptr(), &raw mut pixel_format, &raw mut count) };
+
+    if pixel_format.is_null() {
+        return false;
+    }
+
+    unsafe { CGLDestroyPixelFormat(pixel_format) };
+    result == 0 && count > 0
+}
@@ -0,0 +1,31 @@
+use egui::Context;
+
+/// Describes a monitor.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct MonitorInfo {
+    /// Size of the monitor in physical pixels.
+    pub physical_size: (u32, u32),
+
+    /// Scale factor of the monitor, this is the number of physical pixels per point.
+    pub scale_factor: f32,
+}
+
+/// Returns the monitor the window is currently on, as reported by egui.
+/// egui only reports the size and the scale factor of the monitor.
+pub fn current_monitor(ctx: &Context) -> Option<MonitorInfo> {
+    ctx.input(|input| {
+        let viewport = input.viewport();
+        let scale_factor = viewport.native_pixels_per_point?;
+        let size = viewport.monitor_size? * scale_factor;
+
+        //Monitors are never larger than u32::MAX pixels, the conversion can't truncate.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let physical_size = (size.x.round() as u32, size.y.round() as u32);
+
+        Some(MonitorInfo {
+            physical_size,
+            scale_factor,
+        })
+    })
+}
@@ -0,0 +1,164 @@
+/// The hypervisor of a virtual machine.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum VmType {
+    /// `VMware` Workstation, Fusion or `ESXi`.
+    Vmware,
+
+    /// Oracle `VirtualBox`.
+    VirtualBox,
+
+    /// KVM or QEMU.
+    Kvm,
+
+    /// Microsoft Hyper-V.
+    HyperV,
+
+    /// A hypervisor that is not known, or the hypervisor could not be identified.
+    Unknown,
+}
+
+/// Returns true if the application is shown on a remote display instead of the physical one.
+///
+/// On linux, FreeBSD and OpenBSD this detects the remote sessions and remote X11 displays the built-in detection
+/// selects the software backend for, on linux waypipe sessions too.
+/// On windows this detects RDP sessions, on macOS Screen Sharing and Apple Remote Desktop sessions.
+/// On other platforms this always returns false.
+#[must_use]
+pub fn is_remote_display_session() -> bool {
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
+    if crate::detection::detect_remote_session(&crate::RealPlatformDetector).is_some() {
+        return true;
+    }
+
+    #[cfg(target_os = "linux")]
+    if crate::detection::detect_waypipe(&crate::RealPlatformDetector).is_some() {
+        return true;
+    }
+
+    #[cfg(windows)]
+    if unsafe {
+        windows_sys::Win32::UI::WindowsAndMessaging::GetSystemMetrics(
+            windows_sys::Win32::UI::WindowsAndMessaging::SM_REMOTESESSION,
+        ) != 0
+    } {
+        return true;
+    }
+
+    #[cfg(target_os = "macos")]
+    if crate::macos::is_on_console() == Some(false) {
+        return true;
+    }
+
+    false
+}
+
+/// Returns the hypervisor if the system runs in a virtual machine, None if it runs on a physical machine.
+///
+/// On windows the hypervisor is identified by the cpu, which only works on x86.
+/// Windows with virtualization based security enabled runs on Hyper-V, so this returns `VmType::HyperV`
+/// on such physical machines too. On linux the hypervisor is identified by the dmi vendor and product name.
+/// macOS only reports whether there is a hypervisor, so this returns `VmType::Unknown` for every virtual machine.
+/// On other platforms this always returns None.
+#[must_use]
+pub fn is_virtual_machine() -> Option<VmType> {
+    #[cfg(all(windows, any(target_arch = "x86_64", target_arch = "x86")))]
+    {
+        let hypervisor = raw_cpuid::CpuId::new().get_hypervisor_info()?;
+        Some(match hypervisor.identify() {
+            raw_cpuid::Hypervisor::VMware => VmType::Vmware,
+            raw_cpuid::Hypervisor::HyperV => VmType::HyperV,
+            raw_cpuid::Hypervisor::KVM | raw_cpuid::Hypervisor::QEMU => VmType::Kvm,
+            _ => VmType::Unknown,
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux_virtual_machine()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        crate::macos::is_virtual_machine().then_some(VmType::Unknown)
+    }
+
+    #[cfg(not(any(
+        all(windows, any(target_arch = "x86_64", target_arch = "x86")),
+        target_os = "linux",
+        target_os = "macos"
+    )))]
+    {
+        None
+    }
+}
+
+/// Identifies the hypervisor from the dmi information in sysfs and the cpu flags.
+#[cfg(target_os = "linux")]
+fn linux_virtual_machine() -> Option<VmType> {
+    let read = |path: &str| {
+        std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+    };
+
+    let vendor = read("/sys/class/dmi/id/sys_vendor");
+    let product = read("/sys/class/dmi/id/product_name");
+
+    if vendor.contains("vmware") {
+        return Some(VmType::Vmware);
+    }
+
+    if vendor.contains("innotek") || product.contains("virtualbox") {
+        return Some(VmType::VirtualBox);
+    }
+
+    if vendor.contains("qemu") || product.contains("kvm") {
+        return Some(VmType::Kvm);
+    }
+
+    if vendor.contains("microsoft") && product.contains("virtual machine") {
+        return Some(VmType::HyperV);
+    }
+
+    //The cpu reports the hypervisor flag in every virtual machine, even if the dmi information is hidden.
+    let cpuinfo = read("/proc/cpuinfo");
+    cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("flags"))
+        .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor"))
+        .then_some(VmType::Unknown)
+}
+
+/// Returns whether an opengl context with at least the version can be created.
+///
+/// On windows this creates a small hidden opengl window, like the built-in detection.
+/// This must be called on the main thread, it returns None on other threads.
+/// A hung graphics driver blocks this function, the built-in detection guards against that with a watchdog.
+/// On macOS this checks for a hardware accelerated pixel format, which only works for versions up to 4.1.
+/// Returns None on other platforms, where the check cannot be performed without a window.
+#[must_use]
+#[allow(clippy::missing_const_for_fn)]
+pub fn opengl_version_available(major: u8, minor: u8) -> Option<bool> {
+    #[cfg(windows)]
+    {
+        if main_thread::IsMainThread::OtherThread == main_thread::is_main_thread() {
+            return None;
+        }
+
+        let report = crate::detection::probe_opengl(true, (major, minor));
+        Some(report.backend == crate::Backend::Eframe)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        crate::macos::has_accelerated_gl_version(major, minor)
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        _ = (major, minor);
+        None
+    }
+}
@@ -0,0 +1,171 @@
+use std::path::Path;
+
+/// Access to the environment, the file system and opengl used by the built-in detection.
+///
+/// Every method defaults to querying the real system, so an implementation only overrides what it wants to fake.
+/// This makes it possible to run the detection with a fixed environment,
+/// e.g. in tests that check which backend is selected in a remote session.
+/// Queries that need os specific apis, like the display adapters on windows or the console session on macOS,
+/// are not part of this trait and always query the real system.
+///
+/// See `BackendConfiguration::with_platform_detector`.
+pub trait PlatformDetector: Send + Sync {
+    /// Returns the value of an environment variable. None if it is not set or not valid unicode.
+    fn env_var(&self, key: &str) -> Option<String> {
+        std::env::var(key).ok()
+    }
+
+    /// Returns the names of all environment variables that are valid unicode.
+    fn env_var_names(&self) -> Vec<String> {
+        std::env::vars_os()
+            .filter_map(|(name, _)| name.into_string().ok())
+            .collect()
+    }
+
+    /// Returns the X11 display the application connects to. None if `DISPLAY` is not set.
+    fn display_env(&self) -> Option<String> {
+        self.env_var("DISPLAY")
+    }
+
+    /// Returns true if the file or directory exists.
+    fn file_exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    /// Returns the content of the file. None if it cannot be read.
+    fn read_file(&self, path: &Path) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
+
+    /// Returns the names of the entries of the directory. None if it cannot be read.
+    fn dir_entries(&self, path: &Path) -> Option<Vec<String>> {
+        let entries = std::fs::read_dir(path).ok()?;
+        Some(
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect(),
+        )
+    }
+
+    /// Returns true if the shared library can be loaded. This is only used on linux.
+    fn has_library(&self, name: &str) -> bool {
+        #[cfg(target_os = "linux")]
+        {
+            unsafe { libloading::Library::new(name) }.is_ok()
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = name;
+            false
+        }
+    }
+
+    /// Returns whether an opengl context with at least the version could be created.
+    /// None runs the built-in probe, which creates a hidden glfw window. This is only used on windows.
+    fn create_opengl_context(&self, major: u8, minor: u8) -> Option<bool> {
+        _ = (major, minor);
+        None
+    }
+}
+
+/// Queries the real system. This is the default.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RealPlatformDetector;
+
+impl PlatformDetector for RealPlatformDetector {}
+
+/// Answers the queries from fixed values instead of the real system, so the tests of the detection are hermetic.
+/// Everything that is not set is reported as missing.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+#[derive(Debug, Default, Clone)]
+pub struct FakePlatformDetector {
+    /// The environment variables.
+    env: std::collections::BTreeMap<String, String>,
+
+    /// The content of the files.
+    files: std::collections::BTreeMap<std::path::PathBuf, String>,
+
+    /// The entries of the directories.
+    dirs: std::collections::BTreeMap<std::path::PathBuf, Vec<String>>,
+
+    /// The shared libraries that can be loaded.
+    libraries: Vec<String>,
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+impl FakePlatformDetector {
+    /// A linux desktop with a gpu and the opengl libraries. The detection selects eframe on it.
+    pub fn linux_desktop() -> Self {
+        Self::default()
+            .with_env("DISPLAY", ":0")
+            .with_dir("/dev/dri", &["card0", "renderD128"])
+            .with_library("libEGL.so.1")
+    }
+
+    /// Sets an environment variable.
+    pub fn with_env(mut self, key: &str, value: &str) -> Self {
+        self.env.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Removes an environment variable.
+    pub fn without_env(mut self, key: &str) -> Self {
+        self.env.remove(key);
+        self
+    }
+
+    /// Creates a file with the content.
+    pub fn with_file(mut self, path: &str, content: &str) -> Self {
+        self.files.insert(path.into(), content.to_string());
+        self
+    }
+
+    /// Creates a directory with the entries.
+    pub fn with_dir(mut self, path: &str, entries: &[&str]) -> Self {
+        self.dirs.insert(
+            path.into(),
+            entries.iter().map(ToString::to_string).collect(),
+        );
+        self
+    }
+
+    /// Makes a shared library loadable.
+    pub fn with_library(mut self, name: &str) -> Self {
+        self.libraries.push(name.to_string());
+        self
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+impl PlatformDetector for FakePlatformDetector {
+    fn env_var(&self, key: &str) -> Option<String> {
+        self.env.get(key).cloned()
+    }
+
+    fn env_var_names(&self) -> Vec<String> {
+        self.env.keys().cloned().collect()
+    }
+
+    fn file_exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path) || self.dirs.contains_key(path)
+    }
+
+    fn read_file(&self, path: &Path) -> Option<String> {
+        self.files.get(path).cloned()
+    }
+
+    fn dir_entries(&self, path: &Path) -> Option<Vec<String>> {
+        self.dirs.get(path).cloned()
+    }
+
+    fn has_library(&self, name: &str) -> bool {
+        self.libraries.iter().any(|library| library == name)
+    }
+
+    fn create_opengl_context(&self, major: u8, minor: u8) -> Option<bool> {
+        _ = (major, minor);
+        Some(false)
+    }
+}
@@ -0,0 +1,208 @@
+use crate::Backend;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Name of the cache file inside the cache directory.
+const PROBE_CACHE_FILE_NAME: &str = "backend_probe.cache";
+
+/// Cached probe results older than this are discarded.
+const PROBE_CACHE_MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+/// Returns the seconds since the unix epoch.
+fn now() -> Option<u64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|a| a.as_secs())
+}
+
+/// Reads the cached probe result.
+/// Returns None if there is no cache, if the cache is too old or if the driver fingerprint changed.
+pub fn read(cache_dir: &Path, fingerprint: &str) -> Option<Backend> {
+    let cache_file = cache_dir.join(PROBE_CACHE_FILE_NAME);
+    let content = std::fs::read_to_string(&cache_file).ok()?;
+
+    //Line 1: timestamp, Line 2: backend, Line 3: driver fingerprint
+    let mut lines = content.lines();
+    let timestamp = lines.next()?.parse::<u64>().ok()?;
+    let backend = match lines.next()? {
+        "SoftwareBackend" => Backend::SoftwareBackend,
+        "Eframe" => Backend::Eframe,
+        _ => return None,
+    };
+
+    if lines.next()? != fingerprint {
+        log::debug!("Graphics driver changed, discarding cached opengl probe result.");
+        return None;
+    }
+
+    //A timestamp in the future means the clock was changed, we don't trust the cache then.
+    let age = now()?.checked_sub(timestamp)?;
+    if age > PROBE_CACHE_MAX_AGE.as_secs() {
+        log::debug!("Cached opengl probe result is too old, discarding it.");
+        return None;
+    }
+
+    log::debug!(
+        "Using cached opengl probe result from {}",
+        cache_file.display()
+    );
+    Some(backend)
+}
+
+/// Writes the probe result to the cache.
+pub fn write(cache_dir: &Path, fingerprint: &str, backend: Backend) {
+    let Some(timestamp) = now() else {
+        return;
+    };
+
+    let backend = match backend {
+        Backend::SoftwareBackend => "SoftwareBackend",
+        Backend::Eframe => "Eframe",
+        //The probe only ever selects a built-in backend.
+        Backend::CustomBackend(_) => return,
+    };
+
+    if let Err(e) = std::fs::create_dir_all(cache_dir) {
+        log::warn!(
+            "Failed to cache opengl probe result. Could not create directory {} err={e}",
+            cache_dir.display()
+        );
+        return;
+    }
+
+    let cache_file = cache_dir.join(PROBE_CACHE_FILE_NAME);
+    if let Err(e) = std::fs::write(
+        &cache_file,
+        format!("{timestamp}\n{backend}\n{fingerprint}\n"),
+    ) {
+        log::warn!(
+            "Failed to cache opengl probe result. Could not write file {} err={e}",
+            cache_file.display()
+        );
+    }
+}
+
+/// Converts a nul terminated wide string from a winapi struct to a rust string.
+fn from_wide(wide: &[u16]) -> String {
+    let len = wide.iter().position(|c| *c == 0).unwrap_or(wide.len());
+    String::from_utf16_lossy(&wide[..len])
+}
+
+/// Reads the driver version from the registry key of a display device.
+fn driver_version(device_key: &[u16]) -> Option<String> {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ, RegGetValueW};
+
+    //The device key looks like this: \Registry\Machine\System\CurrentControlSet\Control\Video\{...}\0000
+    let device_key = from_wide(device_key);
+    let prefix = "\\Registry\\Machine\\";
+    let (head, sub_key) = device_key.split_at_checked(prefix.len())?;
+    if !head.eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+
+    let sub_key: Vec<u16> = sub_key.encode_utf16().chain(std::iter::once(0)).collect();
+    let value_name: Vec<u16> = "DriverVersion"
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut buffer = [0u16; 128];
+    let mut buffer_size = u32::try_from(size_of_val(&buffer)).ok()?;
+
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            sub_key.as_ptr(),
+            value_name.as_ptr(),
+            RRF_RT_REG_SZ,
+            std::ptr::null_mut(),
+            buffer.as_mut_ptr().cast(),
+            &raw mut buffer_size,
+        )
+    };
+
+    if result != ERROR_SUCCESS {
+        return None;
+    }
+
+    Some(from_wide(&buffer))
+}
+
+/// A display adapter that is attached to the desktop.
+pub struct DisplayAdapter {
+    /// Name of the adapter, for example "NVIDIA GeForce RTX 3060".
+    pub name: String,
+
+    /// Device id of the adapter, which contains the pci vendor id, for example "PCI\\VEN_10DE&DEV_2504...".
+    pub device_id: String,
+
+    /// Is this the adapter of the primary monitor?
+    pub primary: bool,
+}
+
+/// Returns all display adapters that are attached to the desktop.
+pub fn active_display_adapters() -> Vec<DisplayAdapter> {
+    use windows_sys::Win32::Graphics::Gdi::{
+        DISPLAY_DEVICE_ATTACHED_TO_DESKTOP, DISPLAY_DEVICE_PRIMARY_DEVICE, DISPLAY_DEVICEW,
+        EnumDisplayDevicesW,
+    };
+
+    let mut adapters = Vec::new();
+    let mut index = 0;
+    loop {
+        let mut device: DISPLAY_DEVICEW = unsafe { std::mem::zeroed() };
+        let Ok(cb) = u32::try_from(size_of::<DISPLAY_DEVICEW>()) else {
+            return adapters;
+        };
+        device.cb = cb;
+
+        if unsafe { EnumDisplayDevicesW(std::ptr::null(), index, &raw mut device, 0) } == 0 {
+            return adapters;
+        }
+
+        if device.StateFlags & DISPLAY_DEVICE_ATTACHED_TO_DESKTOP != 0 {
+            adapters.push(DisplayAdapter {
+                name: from_wide(&device.DeviceString),
+                device_id: from_wide(&device.DeviceID),
+                primary: device.StateFlags & DISPLAY_DEVICE_PRIMARY_DEVICE != 0,
+            });
+        }
+
+        index += 1;
+    }
+}
+
+/// Computes a fingerprint of all display adapters and their driver versions.
+/// If the fingerprint changes, then the cached probe result is no longer valid.
+pub fn driver_fingerprint() -> Option<String> {
+    use windows_sys::Win32::Graphics::Gdi::{DISPLAY_DEVICEW, EnumDisplayDevicesW};
+
+    let mut fingerprint = String::new();
+    let mut index = 0;
+    loop {
+        let mut device: DISPLAY_DEVICEW = unsafe { std::mem::zeroed() };
+        device.cb = u32::try_from(size_of::<DISPLAY_DEVICEW>()).ok()?;
+
+        if unsafe { EnumDisplayDevicesW(std::ptr::null(), index, &raw mut device, 0) } == 0 {
+            break;
+        }
+
+        fingerprint.push_str(&from_wide(&device.DeviceString));
+        fingerprint.push('|');
+        fingerprint.push_str(&from_wide(&device.DeviceID));
+        fingerprint.push('|');
+        fingerprint.push_str(&driver_version(&device.DeviceKey).unwrap_or_default());
+        fingerprint.push(';');
+
+        index += 1;
+    }
+
+    if fingerprint.is_empty() {
+        return None;
+    }
+
+    //The fingerprint is stored in a single line.
+    Some(fingerprint.replace(['\r', '\n'], " "))
+}
@@ -0,0 +1,183 @@
+use crate::Backend;
+use std::sync::{Mutex, PoisonError};
+
+/// The decisive reason why a backend was selected.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum BackendSelectionReason {
+    /// Nothing indicated that the preferred backend of the platform would not work.
+    Default,
+
+    /// The backend was chosen by calling `overwrite_backend`.
+    Overwritten,
+
+    /// The app runs in a Windows remote desktop session.
+    RemoteDesktopSession,
+
+    /// The app runs in a VM with the `VMWare` opengl driver installed.
+    VmwareDriverPresent,
+
+    /// The app runs in a VM with the `VirtualBox` opengl driver installed.
+    VirtualBoxDriverPresent,
+
+    /// The X11 display is a remote display.
+    RemoteX11Display,
+
+    /// The opengl version on the system is too old or opengl is not available at all.
+    GlProbeFailed,
+
+    /// The child process that performed the opengl probe crashed.
+    GlProbeCrashed,
+
+    /// The opengl probe did not finish within the timeout, the graphics driver probably hangs.
+    GlProbeTimedOut,
+
+    /// The previous run with eframe did not exit cleanly.
+    PreviousRunCrashed,
+
+    /// eframe failed to initialize and the process was relaunched with the software backend.
+    EframeFailed,
+
+    /// A probe registered with `register_probe` selected the backend.
+    CustomProbe,
+
+    /// A custom backend is registered with `BackendConfiguration::register_custom_backend`.
+    CustomBackend,
+
+    /// The detected backend is not allowed by `BackendConfiguration::with_allowed_backends`.
+    NotAllowed,
+
+    /// The backend was selected with the `--egui-backend` command line argument.
+    CommandLine,
+
+    /// The backend was stored with `BackendInterop::set_preferred_backend` during a previous run.
+    StoredPreference,
+
+    /// There is no gpu render node in `/dev/dri`.
+    NoGpuRenderNode,
+
+    /// The opengl libraries could not be loaded.
+    GlLibraryMissing,
+
+    /// The X11 display is forwarded over ssh.
+    SshForwarding,
+
+    /// The application runs in an XRDP session.
+    XrdpSession,
+
+    /// The application runs in an x2go session.
+    X2goSession,
+
+    /// The application runs in a VNC session.
+    VncSession,
+
+    /// The application runs in a `NoMachine` session.
+    NoMachineSession,
+
+    /// The application runs in a Chrome Remote Desktop session.
+    ChromeRemoteDesktopSession,
+
+    /// The application runs in a wayland session forwarded with waypipe.
+    WaypipeSession,
+
+    /// The application runs in WSL1, which has no gpu access.
+    Wsl1,
+
+    /// The application runs in WSL2 with `WSLg`, which provides a gpu.
+    Wslg,
+
+    /// The application runs in WSL2 without `WSLg`, so the display is forwarded to an X server on windows.
+    WslWithoutWslg,
+
+    /// opengl is only provided by a software renderer like llvmpipe, which is slower than the software backend.
+    /// This is only detected on Windows.
+    SoftwareGlRenderer,
+
+    /// The application runs in a Hyper-V virtual machine that only has the Hyper-V video adapter.
+    HyperVVideoAdapter,
+
+    /// No driver is installed for the gpu, windows uses the Microsoft Basic Display Adapter.
+    BasicDisplayAdapter,
+
+    /// The gpu is an old intel gpu with an unreliable opengl driver.
+    OldIntelGpu,
+
+    /// macOS runs in a virtual machine without hardware accelerated opengl.
+    VmWithoutGlAcceleration,
+}
+
+/// Describes which backend was selected and why.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct BackendSelectionReport {
+    /// The selected backend.
+    pub backend: Backend,
+
+    /// The decisive reason why the backend was selected.
+    pub reason: BackendSelectionReason,
+
+    /// Free-form details about the decision, for example the value of an environment variable. May be empty.
+    pub detail: String,
+}
+
+/// Describes whether a backend works on this system, as returned by `probe_backends`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub struct BackendProbeResult {
+    /// The probed backend.
+    pub backend: Backend,
+
+    /// Does the backend work on this system?
+    pub available: bool,
+
+    /// The reason why the backend is unavailable, `BackendSelectionReason::Default` if it is available.
+    pub reason: BackendSelectionReason,
+
+    /// Free-form details about the result, for example the opengl renderer. May be empty.
+    pub detail: String,
+}
+
+/// The report of the current selection.
+static REPORT: Mutex<Option<BackendSelectionReport>> = Mutex::new(None);
+
+/// Returns the report of why the current backend was selected.
+/// Returns None if no backend has been selected yet.
+pub fn selection_report() -> Option<BackendSelectionReport> {
+    REPORT
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Returns the decisive reason why the current backend was selected.
+/// Returns None if no backend has been selected yet.
+/// This is a shorthand for the reason of `selection_report`.
+#[must_use]
+pub fn get_backend_selection_reason() -> Option<BackendSelectionReason> {
+    selection_report().map(|report| report.reason)
+}
+
+/// Records the report of the current selection.
+pub fn set_selection_report(report: BackendSelectionReport) {
+    log::debug!(
+        "Selected backend {:?} reason={:?} detail={}",
+        report.backend,
+        report.reason,
+        report.detail
+    );
+
+    *REPORT.lock().unwrap_or_else(PoisonError::into_inner) = Some(report);
+}
+
+/// Shorthand to create a report.
+pub fn report(
+    backend: Backend,
+    reason: BackendSelectionReason,
+    detail: impl Into<String>,
+) -> BackendSelectionReport {
+    BackendSelectionReport {
+        backend,
+        reason,
+        detail: detail.into(),
+    }
+}
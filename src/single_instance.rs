@@ -0,0 +1,152 @@
+/// Lock that is held while the app is running. Dropping it releases the lock.
+pub struct InstanceLock {
+    /// The locked lock file.
+    #[cfg(not(windows))]
+    _file: std::fs::File,
+
+    /// The handle of the named mutex.
+    #[cfg(windows)]
+    handle: windows_sys::Win32::Foundation::HANDLE,
+}
+
+/// Result of acquiring the lock of the app.
+pub enum InstanceLockState {
+    /// This is the only instance, it holds the lock until it is dropped.
+    Acquired(InstanceLock),
+
+    /// Another instance holds the lock.
+    AlreadyRunning,
+
+    /// The lock could not be created, the app is allowed to run without enforcing a single instance.
+    NotEnforced,
+}
+
+/// Returns the app name with all characters that are not allowed in file or mutex names replaced.
+fn sanitized(app_name: &str) -> String {
+    app_name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Returns the path of the lock file in a directory only the user can write to.
+/// This is the runtime directory of the user, or the storage directory of the app if there is none.
+/// The shared temporary directory is not used, another user could take the lock there.
+#[cfg(not(windows))]
+fn lock_path(app_name: &str) -> Option<std::path::PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Some(
+            std::path::PathBuf::from(dir).join(format!("{}.instance.lock", sanitized(app_name))),
+        );
+    }
+
+    #[cfg(feature = "persistence")]
+    {
+        let dir = eframe::storage_dir(app_name)?;
+        _ = std::fs::create_dir_all(&dir);
+        Some(dir.join("instance.lock"))
+    }
+
+    #[cfg(not(feature = "persistence"))]
+    None
+}
+
+/// Acquires the lock of the app by taking an exclusive lock of a file in a directory of the user.
+/// If the lock file cannot be created, the app is allowed to run.
+#[cfg(not(windows))]
+pub fn acquire(app_name: &str) -> InstanceLockState {
+    use fs4::fs_std::FileExt;
+
+    let Some(path) = lock_path(app_name) else {
+        log::warn!(
+            "There is no directory for the instance lock file, not enforcing a single instance."
+        );
+        return InstanceLockState::NotEnforced;
+    };
+
+    let Ok(file) = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .inspect_err(|e| {
+            log::warn!(
+                "Failed to create the instance lock file {}, not enforcing a single instance. err={e}",
+                path.display()
+            );
+        })
+    else {
+        return InstanceLockState::NotEnforced;
+    };
+
+    if file.try_lock_exclusive().is_err() {
+        return InstanceLockState::AlreadyRunning;
+    }
+
+    InstanceLockState::Acquired(InstanceLock { _file: file })
+}
+
+/// Acquires the lock of the app by creating a named mutex in the session of the user.
+/// If the mutex cannot be created, the app is allowed to run.
+#[cfg(windows)]
+pub fn acquire(app_name: &str) -> InstanceLockState {
+    use windows_sys::Win32::Foundation::{CloseHandle, ERROR_ALREADY_EXISTS, GetLastError};
+    use windows_sys::Win32::System::Threading::CreateMutexW;
+
+    let name = format!("Local\\egui_backend_selector_{}", sanitized(app_name))
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect::<Vec<u16>>();
+
+    let handle = unsafe { CreateMutexW(std::ptr::null(), 0, name.as_ptr()) };
+    if handle.is_null() {
+        log::warn!("Failed to create the instance mutex, not enforcing a single instance.");
+        return InstanceLockState::NotEnforced;
+    }
+
+    if unsafe { GetLastError() } == ERROR_ALREADY_EXISTS {
+        unsafe { CloseHandle(handle) };
+        return InstanceLockState::AlreadyRunning;
+    }
+
+    InstanceLockState::Acquired(InstanceLock { handle })
+}
+
+#[cfg(windows)]
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        unsafe { windows_sys::Win32::Foundation::CloseHandle(self.handle) };
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    /// A second instance is refused until the first one releases the lock.
+    #[test]
+    fn second_instance_is_refused() {
+        let app_name = format!("egui_backend_selector_instance_test_{}", std::process::id());
+
+        let InstanceLockState::Acquired(lock) = acquire(&app_name) else {
+            panic!("the first instance did not get the lock");
+        };
+        assert!(matches!(
+            acquire(&app_name),
+            InstanceLockState::AlreadyRunning
+        ));
+
+        drop(lock);
+        assert!(matches!(acquire(&app_name), InstanceLockState::Acquired(_)));
+
+        if let Some(path) = lock_path(&app_name) {
+            _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use egui::epaint::ClippedShape;
+use egui::epaint::ImageDelta;
+use egui::{ColorImage, Context, FullOutput, ImageData, TextureId, TextureOptions};
+use egui_software_backend::{BufferMutRef, ColorFieldOrder, EguiSoftwareRender};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// egui plugin that takes the screenshots of the software backend.
+/// The software backend ignores `egui::ViewportCommand::Screenshot` and keeps its frame buffer private,
+/// so the output of the requested frame is rendered a second time into an image.
+/// The renderer only receives the changes of the textures of each frame, so the plugin keeps a copy of every texture.
+#[derive(Default)]
+pub struct ScreenshotPlugin {
+    /// Copy of every texture egui uploaded to the backend, with the changes of all frames applied.
+    textures: HashMap<TextureId, (Arc<ColorImage>, TextureOptions)>,
+
+    /// Size of the window in pixels if the output of the current frame should be captured.
+    capture: Option<[usize; 2]>,
+
+    /// The output of the captured frame until it is rendered by `take_screenshot`.
+    captured: Option<CapturedFrame>,
+}
+
+/// Output of a frame that is rendered into a screenshot.
+struct CapturedFrame {
+    /// The shapes egui painted in the frame.
+    shapes: Vec<ClippedShape>,
+
+    /// The pixels per point of the frame.
+    pixels_per_point: f32,
+
+    /// Size of the window in pixels.
+    size: [usize; 2],
+
+    /// Textures freed at the end of the frame, they are only removed once the frame is rendered.
+    freed: Vec<TextureId>,
+}
+
+impl ScreenshotPlugin {
+    /// Captures the output of the current frame, it is rendered by `take_screenshot` in the next frame.
+    /// Does nothing if the plugin was not added to the context.
+    pub fn request(ctx: &Context) {
+        let size = (ctx.viewport_rect().size() * ctx.pixels_per_point()).round();
+        //The size of the window is small and positive, the conversion can't truncate.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let size = [size.x as usize, size.y as usize];
+        ctx.with_plugin(|plugin: &mut Self| plugin.capture = Some(size));
+    }
+
+    /// Renders the frame captured by calling `request`.
+    /// Returns None if no frame was captured since the last call.
+    pub fn take_screenshot(ctx: &Context) -> Option<Arc<ColorImage>> {
+        let (frame, textures) = ctx
+            .with_plugin(|plugin: &mut Self| {
+                let frame = plugin.captured.take()?;
+                let textures = egui::TexturesDelta {
+                    set: plugin
+                        .textures
+                        .iter()
+                        .map(|(id, (image, options))| {
+                            (*id, ImageDelta::full(Arc::clone(image), *options))
+                        })
+                        .collect(),
+                    free: Vec::new(),
+                };
+
+                for id in &frame.freed {
+                    plugin.textures.remove(id);
+                }
+
+                Some((frame, textures))
+            })
+            .flatten()?;
+
+        let [width, height] = frame.size;
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let primitives = ctx.tessellate(frame.shapes, frame.pixels_per_point);
+        //The software backend clears its buffer to black before rendering as well.
+        let mut pixels = vec![[0u8; 4]; width * height];
+        EguiSoftwareRender::new(ColorFieldOrder::Rgba).render(
+            &mut BufferMutRef::new(&mut pixels, width, height),
+            &primitives,
+            &textures,
+            frame.pixels_per_point,
+        );
+
+        //The window shows the colors without blending them with what is behind it, so the screenshot is opaque too.
+        Some(Arc::new(ColorImage::new(
+            frame.size,
+            pixels
+                .into_iter()
+                .map(|[r, g, b, _]| egui::Color32::from_rgb(r, g, b))
+                .collect(),
+        )))
+    }
+
+    /// Applies the change of a texture to the copy of the texture.
+    fn apply(&mut self, id: TextureId, delta: &ImageDelta) {
+        let ImageData::Color(image) = &delta.image;
+        let Some([x, y]) = delta.pos else {
+            self.textures.insert(id, (Arc::clone(image), delta.options));
+            return;
+        };
+
+        let Some((texture, options)) = self.textures.get_mut(&id) else {
+            return;
+        };
+
+        let texture = Arc::make_mut(texture);
+        let width = image.width();
+        for (row, patch) in image.pixels.chunks_exact(width).enumerate() {
+            let start = (y + row) * texture.width() + x;
+            texture.pixels[start..start + width].copy_from_slice(patch);
+        }
+
+        *options = delta.options;
+    }
+}
+
+impl egui::Plugin for ScreenshotPlugin {
+    fn debug_name(&self) -> &'static str {
+        "egui_backend_selector::ScreenshotPlugin"
+    }
+
+    fn output_hook(&mut self, output: &mut FullOutput) {
+        for (id, delta) in &output.textures_delta.set {
+            self.apply(*id, delta);
+        }
+
+        if let Some(size) = self.capture.take() {
+            self.captured = Some(CapturedFrame {
+                shapes: output.shapes.clone(),
+                pixels_per_point: output.pixels_per_point,
+                size,
+                freed: output.textures_delta.free.clone(),
+            });
+            return;
+        }
+
+        for id in &output.textures_delta.free {
+            self.textures.remove(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScreenshotPlugin;
+
+    /// Runs one frame that fills the left half of the window with a red panel
+    /// and the right half with the texture.
+    fn run_frame(ctx: &egui::Context, texture: &egui::TextureHandle, capture: bool) {
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(64.0, 32.0),
+            )),
+            ..egui::RawInput::default()
+        };
+
+        _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::NONE.fill(egui::Color32::RED))
+                .show(ctx, |ui| {
+                    ui.painter().image(
+                        texture.id(),
+                        egui::Rect::from_min_max(egui::pos2(32.0, 0.0), egui::pos2(64.0, 32.0)),
+                        egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0)),
+                        egui::Color32::WHITE,
+                    );
+                });
+            if capture {
+                ScreenshotPlugin::request(ctx);
+            }
+        });
+    }
+
+    /// The captured frame is rendered with the size of the window in pixels,
+    /// including textures that were uploaded in earlier frames.
+    #[test]
+    fn captured_frame_is_rendered() {
+        let ctx = egui::Context::default();
+        ctx.add_plugin(ScreenshotPlugin::default());
+        let mut texture = ctx.load_texture(
+            "green",
+            egui::ColorImage::filled([4, 4], egui::Color32::GREEN),
+            egui::TextureOptions::NEAREST,
+        );
+
+        run_frame(&ctx, &texture, false);
+        assert!(ScreenshotPlugin::take_screenshot(&ctx).is_none());
+
+        run_frame(&ctx, &texture, true);
+        let screenshot = ScreenshotPlugin::take_screenshot(&ctx).expect("no screenshot");
+        let pixels_per_point = ctx.pixels_per_point();
+        //The size of the window is small and positive, the conversion can't truncate.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let [width, height] = [
+            (64.0 * pixels_per_point).round() as usize,
+            (32.0 * pixels_per_point).round() as usize,
+        ];
+        assert_eq!(screenshot.size, [width, height]);
+        assert_eq!(screenshot.pixels[0], egui::Color32::RED);
+        assert_eq!(screenshot.pixels[width - 1], egui::Color32::GREEN);
+
+        //Each screenshot is only rendered once.
+        assert!(ScreenshotPlugin::take_screenshot(&ctx).is_none());
+
+        //Partial updates of the texture are applied to the copy of the plugin.
+        texture.set_partial(
+            [0, 0],
+            egui::ColorImage::filled([4, 4], egui::Color32::BLUE),
+            egui::TextureOptions::NEAREST,
+        );
+        run_frame(&ctx, &texture, true);
+        let screenshot = ScreenshotPlugin::take_screenshot(&ctx).expect("no screenshot");
+        assert_eq!(screenshot.pixels[width - 1], egui::Color32::BLUE);
+    }
+}
@@ -0,0 +1,1128 @@
+use eframe::Storage;
+
+/// Storage key of the schema version of the persisted data.
+#[cfg(not(target_arch = "wasm32"))]
+const SCHEMA_VERSION_KEY: &str = "__egui_backend_selector::schema_version";
+
+/// Prefix of values that are stored deflate compressed and base64 encoded.
+/// egui and eframe never store values starting with a NUL character.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+const COMPRESSED_PREFIX: &str = "\0egui_backend_selector::deflate:";
+
+/// Header of storage files that are deflate compressed.
+/// It starts with a NUL byte, which is never valid at the start of a RON or JSON file.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+const DEFLATE_FILE_MAGIC: &[u8] = b"\0egui_backend_selector::deflate\n";
+
+/// Header of storage files that are zstd compressed.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+const ZSTD_FILE_MAGIC: &[u8] = b"\0egui_backend_selector::zstd\n";
+
+/// Migrates persisted data of an older version of the app to a newer format.
+pub trait StorageMigration {
+    /// Migrates the data in the storage.
+    /// `version` is the current schema version of the data, it is 0 if no version was stored yet.
+    /// Returns the schema version of the data after the migration.
+    /// Migrations that do not apply to the current version should return `version` unchanged.
+    fn migrate(&self, version: u32, storage: &mut dyn Storage) -> u32;
+}
+
+/// Runs all migrations in order and stores the resulting schema version.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run_migrations(
+    storage: &mut dyn Storage,
+    migrations: &[std::sync::Arc<dyn StorageMigration>],
+) {
+    if migrations.is_empty() {
+        return;
+    }
+
+    let initial_version = storage
+        .get_string(SCHEMA_VERSION_KEY)
+        .and_then(|version| version.parse::<u32>().ok())
+        .unwrap_or(0);
+
+    let mut version = initial_version;
+    for migration in migrations {
+        version = migration.migrate(version, storage);
+    }
+
+    if version != initial_version {
+        log::info!("Migrated application state from schema version {initial_version} to {version}");
+        storage.set_string(SCHEMA_VERSION_KEY, version.to_string());
+    }
+}
+
+/// Additional functionality of the storage provided by the backend selector.
+pub trait StorageExt: Storage {
+    /// Returns all keys that are currently stored.
+    fn keys(&self) -> Vec<String>;
+
+    /// Removes all keys. The storage will be empty once it is flushed.
+    fn clear(&mut self);
+
+    /// Blocks until all data that was flushed has been written to disk.
+    /// Storages that write synchronously in `flush` do not need to implement this.
+    fn wait_for_flush(&self) {}
+
+    /// Returns true if values changed since the last flush or writing the flushed values failed.
+    /// Storages that do not track changes return false.
+    fn is_dirty(&self) -> bool {
+        false
+    }
+}
+
+/// Storage that keeps the data in memory, nothing is written to disk.
+/// This is useful for tests, see `BackendConfiguration::with_storage`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct MemoryStorage {
+    /// The stored data.
+    kv: std::collections::HashMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.kv.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.kv.insert(key.to_string(), value);
+    }
+
+    fn flush(&mut self) {}
+}
+
+impl StorageExt for MemoryStorage {
+    fn keys(&self) -> Vec<String> {
+        self.kv.keys().cloned().collect()
+    }
+
+    fn clear(&mut self) {
+        self.kv.clear();
+    }
+}
+
+/// File format of the storage.
+/// Both formats store the same flat map of string keys to string values.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum StorageFormat {
+    /// RON, the format eframe uses. The data is stored in `app.ron`.
+    #[default]
+    Ron,
+
+    /// JSON. The data is stored in `app.json`.
+    #[cfg(feature = "storage-json")]
+    Json,
+}
+
+/// Compression of the whole storage file.
+///
+/// This is independent of `BackendConfiguration::with_storage_compression` of the `storage-compression` feature,
+/// which only compresses single large values and keeps the file readable as plain RON or JSON.
+/// Both use deflate and can be combined, but values that are already compressed hardly shrink any further.
+///
+/// Compressed files start with a header that names the compression, so they are read regardless of
+/// the configured compression, and changing it converts the file on the next flush.
+/// Versions of the app that do not know the compression cannot read the file and start with an empty state.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum StorageCompression {
+    /// The file is not compressed.
+    #[default]
+    None,
+
+    /// The file is deflate compressed.
+    #[cfg(feature = "compression")]
+    Deflate,
+
+    /// The file is zstd compressed, which is faster and smaller than deflate.
+    #[cfg(feature = "compression")]
+    Zstd,
+}
+
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+impl StorageCompression {
+    /// Compresses the content of the storage file and prepends the header of the compression.
+    #[allow(clippy::unnecessary_wraps)]
+    fn compress(self, data: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        match self {
+            Self::None => Ok(data),
+            #[cfg(feature = "compression")]
+            Self::Deflate => Ok(deflate(DEFLATE_FILE_MAGIC.to_vec(), &data)?),
+            #[cfg(feature = "compression")]
+            Self::Zstd => {
+                let mut compressed = ZSTD_FILE_MAGIC.to_vec();
+                compressed.extend(zstd::encode_all(data.as_slice(), 0)?);
+                Ok(compressed)
+            }
+        }
+    }
+
+    /// Decompresses the content of a storage file if it starts with the header of a compression.
+    fn decompress(data: Vec<u8>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let (compression, compressed) =
+            if let Some(compressed) = data.strip_prefix(DEFLATE_FILE_MAGIC) {
+                ("deflate", compressed)
+            } else if let Some(compressed) = data.strip_prefix(ZSTD_FILE_MAGIC) {
+                ("zstd", compressed)
+            } else {
+                return Ok(data);
+            };
+
+        #[cfg(feature = "compression")]
+        {
+            if compression == "deflate" {
+                Ok(inflate(compressed)?)
+            } else {
+                Ok(zstd::decode_all(compressed)?)
+            }
+        }
+
+        #[cfg(not(feature = "compression"))]
+        {
+            _ = compressed;
+            Err(format!(
+                "the file is {compression} compressed, but the compression feature is disabled"
+            )
+            .into())
+        }
+    }
+}
+
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+impl StorageFormat {
+    /// All formats that are available.
+    const ALL: &[Self] = &[
+        Self::Ron,
+        #[cfg(feature = "storage-json")]
+        Self::Json,
+    ];
+
+    /// Name of the file inside the storage directory.
+    const fn file_name(self) -> &'static str {
+        match self {
+            Self::Ron => "app.ron",
+            #[cfg(feature = "storage-json")]
+            Self::Json => "app.json",
+        }
+    }
+
+    /// Reads the key value map.
+    fn read(
+        self,
+        reader: impl std::io::Read,
+    ) -> Result<std::collections::HashMap<String, String>, Box<dyn std::error::Error>> {
+        match self {
+            Self::Ron => Ok(ron::de::from_reader(reader)?),
+            #[cfg(feature = "storage-json")]
+            Self::Json => Ok(serde_json::from_reader(reader)?),
+        }
+    }
+
+    /// Writes the key value map.
+    fn write(
+        self,
+        writer: impl std::io::Write,
+        kv: &std::collections::HashMap<String, String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Self::Ron => Ok(ron::Options::default().to_io_writer_pretty(
+                writer,
+                kv,
+                ron::ser::PrettyConfig::default(),
+            )?),
+            #[cfg(feature = "storage-json")]
+            Self::Json => Ok(serde_json::to_writer_pretty(writer, kv)?),
+        }
+    }
+}
+
+/// "eframe" compatible Key Value Storage implementation.
+///
+/// The file is written atomically by writing a temporary file and renaming it,
+/// and the previous file is kept as a backup which is used if the file cannot be read.
+/// An advisory lock is held while the storage is open, a second instance only gets read-only storage.
+/// Like the file storage of eframe, the file is written on a background thread, so flushing does not block a frame.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+pub struct KVStorage {
+    /// The state that was loaded/modified and flush will write to disk.
+    kv: std::collections::HashMap<String, String>,
+
+    /// Did we change anything?
+    dirty: bool,
+
+    /// The locked lock file, it is held until the storage is dropped.
+    /// None if another instance holds the lock, then nothing is written.
+    _lock: Option<std::fs::File>,
+
+    /// Writes the flushed state to disk.
+    writer: Writer,
+
+    /// Values longer than this number of bytes are compressed. None disables compression.
+    #[cfg(feature = "storage-compression")]
+    compression_threshold: Option<usize>,
+}
+
+/// Deflate compresses the data and appends it to `output`.
+/// This is shared by the compression of single values and of the whole file.
+#[cfg(any(feature = "storage-compression", feature = "compression"))]
+fn deflate(output: Vec<u8>, data: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::DeflateEncoder::new(output, flate2::Compression::default());
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// Decompresses deflate compressed data.
+#[cfg(any(feature = "storage-compression", feature = "compression"))]
+fn inflate(compressed: &[u8]) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut decompressed = Vec::new();
+    flate2::read::DeflateDecoder::new(compressed).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// Compresses the value and returns it with the prefix of compressed values.
+/// Returns None if compressing the value fails.
+#[cfg(feature = "storage-compression")]
+fn compress_value(value: &str) -> Option<String> {
+    use base64::Engine;
+
+    let compressed = deflate(Vec::new(), value.as_bytes()).ok()?;
+
+    let mut encoded = COMPRESSED_PREFIX.to_string();
+    base64::engine::general_purpose::STANDARD.encode_string(compressed, &mut encoded);
+    Some(encoded)
+}
+
+/// Decompresses a value that was stored without the prefix of compressed values.
+/// Returns None if the value is corrupted, the error is logged.
+#[cfg(feature = "storage-compression")]
+fn decompress_value(encoded: &str) -> Option<String> {
+    use base64::Engine;
+
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .inspect_err(|e| {
+            log::error!("Ignoring a corrupted compressed value of the storage err={e}");
+        })
+        .ok()?;
+
+    inflate(&compressed)
+        .map_err(|e| e.to_string())
+        .and_then(|value| String::from_utf8(value).map_err(|e| e.to_string()))
+        .inspect_err(|e| {
+            log::error!("Ignoring a corrupted compressed value of the storage err={e}");
+        })
+        .ok()
+}
+
+/// Without the storage-compression feature compressed values cannot be read, they are treated as missing.
+#[cfg(all(
+    feature = "persistence",
+    not(feature = "storage-compression"),
+    not(target_arch = "wasm32")
+))]
+fn decompress_value(encoded: &str) -> Option<String> {
+    _ = encoded;
+    log::warn!(
+        "Ignoring a compressed value of the storage, the storage-compression feature is disabled."
+    );
+    None
+}
+
+/// Writes the key value map to the storage file.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+#[derive(Clone)]
+struct FileWriter {
+    /// Path to the file where we will save the data.
+    file: std::path::PathBuf,
+
+    /// Format of the file.
+    format: StorageFormat,
+
+    /// Compression of the file.
+    compression: StorageCompression,
+
+    /// Can the file be read? A file that cannot be read must not replace the backup.
+    file_valid: bool,
+}
+
+/// How the flushed state is written to disk.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+enum Writer {
+    /// Another instance owns the storage, we only have read access.
+    ReadOnly,
+
+    /// The writer thread could not be started, the file is written in `flush`.
+    Sync(FileWriter),
+
+    /// The file is written by the writer thread.
+    Thread {
+        /// Queue of the writer thread.
+        queue: std::sync::Arc<WriterQueue>,
+
+        /// The writer thread, it exits once the queue is closed and drained.
+        thread: Option<std::thread::JoinHandle<()>>,
+    },
+}
+
+/// Queue shared between the storage and its writer thread.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+#[derive(Default)]
+struct WriterQueue {
+    /// State of the queue.
+    state: std::sync::Mutex<WriterState>,
+
+    /// Notified whenever the state changes.
+    condvar: std::sync::Condvar,
+}
+
+/// State of the queue of the writer thread.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+#[derive(Default)]
+struct WriterState {
+    /// The latest flushed state that was not written yet.
+    /// Flushing again before the writer thread takes it replaces it, so rapid flushes only write once.
+    pending: Option<std::collections::HashMap<String, String>>,
+
+    /// Is the writer thread currently writing?
+    writing: bool,
+
+    /// Did the last write fail? The state is written again on the next flush.
+    write_failed: bool,
+
+    /// The storage was dropped, the writer thread exits once nothing is pending.
+    closed: bool,
+}
+
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+impl WriterQueue {
+    /// Locks the state of the queue.
+    fn lock(&self) -> std::sync::MutexGuard<'_, WriterState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Waits until the condvar is notified.
+    fn wait<'a>(
+        &self,
+        guard: std::sync::MutexGuard<'a, WriterState>,
+    ) -> std::sync::MutexGuard<'a, WriterState> {
+        self.condvar
+            .wait(guard)
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Hands the state to the writer thread.
+    fn push(&self, kv: std::collections::HashMap<String, String>) {
+        self.lock().pending = Some(kv);
+        self.condvar.notify_all();
+    }
+
+    /// Blocks until the writer thread has written everything that was pushed.
+    fn drain(&self) {
+        drop(
+            self.condvar
+                .wait_while(self.lock(), |state| {
+                    state.pending.is_some() || state.writing
+                })
+                .unwrap_or_else(std::sync::PoisonError::into_inner),
+        );
+    }
+
+    /// Returns true if the last write failed and nothing newer was pushed since.
+    fn write_failed(&self) -> bool {
+        let state = self.lock();
+        state.write_failed && state.pending.is_none()
+    }
+
+    /// Tells the writer thread to exit once it has written everything that was pushed.
+    fn close(&self) {
+        self.lock().closed = true;
+        self.condvar.notify_all();
+    }
+
+    /// Main loop of the writer thread.
+    fn run(&self, mut writer: FileWriter) {
+        loop {
+            let kv = {
+                let mut state = self.lock();
+                loop {
+                    if let Some(kv) = state.pending.take() {
+                        state.writing = true;
+                        break kv;
+                    }
+
+                    if state.closed {
+                        return;
+                    }
+
+                    state = self.wait(state);
+                }
+            };
+
+            let written = writer.write(&kv);
+
+            let mut state = self.lock();
+            state.writing = false;
+            state.write_failed = !written;
+            drop(state);
+            self.condvar.notify_all();
+        }
+    }
+}
+
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+impl FileWriter {
+    /// Writes the key value map to disk, errors are logged.
+    /// Returns true if the file was written.
+    fn write(&mut self, kv: &std::collections::HashMap<String, String>) -> bool {
+        if let Some(parent) = self.file.parent()
+            && !parent.exists()
+        {
+            _ = std::fs::create_dir_all(parent);
+        }
+
+        if let Err(e) = self.write_file(kv) {
+            log::error!(
+                "Failed to save application state. Could not write file {} err={e}",
+                self.file.display()
+            );
+            return false;
+        }
+
+        self.file_valid = true;
+        true
+    }
+
+    /// Writes the key value map to a temporary file and renames it over the storage file.
+    /// The previous storage file is kept as a backup.
+    fn write_file(
+        &self,
+        kv: &std::collections::HashMap<String, String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Write;
+
+        let mut data = Vec::new();
+        self.format.write(&mut data, kv)?;
+        let data = self.compression.compress(data)?;
+
+        let temp_file = with_suffix(&self.file, ".tmp");
+        let mut writer = std::fs::File::create(&temp_file)?;
+        writer.write_all(&data)?;
+        writer.sync_all()?;
+
+        if self.file_valid && self.file.exists() {
+            std::fs::copy(&self.file, with_suffix(&self.file, ".bak"))?;
+        }
+
+        //Some file systems, like network shares, cannot replace a file by renaming another one onto it.
+        //The file is written in place there, the backup still holds the previous state if that is interrupted.
+        if let Err(e) = std::fs::rename(&temp_file, &self.file) {
+            log::warn!(
+                "Failed to replace {} atomically, writing it in place err={e}",
+                self.file.display()
+            );
+
+            let mut writer = std::fs::File::create(&self.file)?;
+            writer.write_all(&data)?;
+            writer.sync_all()?;
+            _ = std::fs::remove_file(&temp_file);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the path of the file with the suffix appended to its file name.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+fn with_suffix(file: &std::path::Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    file.with_file_name(name)
+}
+
+/// Takes the advisory lock of the storage file.
+/// Returns None if another instance holds the lock or the lock file cannot be created.
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+fn lock(file: &std::path::Path) -> Option<std::fs::File> {
+    use fs4::fs_std::FileExt;
+
+    let lock_file = with_suffix(file, ".lock");
+    if let Some(parent) = lock_file.parent()
+        && !parent.exists()
+    {
+        _ = std::fs::create_dir_all(parent);
+    }
+
+    let lock = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_file)
+        .inspect_err(|e| {
+            log::warn!(
+                "Failed to create the lock file {}, changes to the application state will not be saved. err={e}",
+                lock_file.display()
+            );
+        })
+        .ok()?;
+
+    if let Err(e) = lock.try_lock_exclusive() {
+        log::warn!(
+            "The application state in {} is used by another instance, changes will not be saved. err={e}",
+            file.display()
+        );
+        return None;
+    }
+
+    Some(lock)
+}
+
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+impl KVStorage {
+    /// Constructor
+    /// `legacy_file` is the storage file of eframe, it is imported once if the directory contains no storage yet.
+    /// Compressed files are always read, `compression` is used when the file is written.
+    /// `file_name` is relative to `data_dir`, None uses the name of the format.
+    /// The state stored in another format is only converted if the name of the format is used.
+    pub fn new(
+        data_dir: &std::path::Path,
+        file_name: Option<&std::path::Path>,
+        format: StorageFormat,
+        compression: StorageCompression,
+        legacy_file: Option<&std::path::Path>,
+    ) -> Option<Self> {
+        let file =
+            data_dir.join(file_name.unwrap_or_else(|| std::path::Path::new(format.file_name())));
+        let backup = with_suffix(&file, ".bak");
+
+        let lock = lock(&file);
+
+        //Once the imported state is flushed the file exists, so nothing is imported twice.
+        if !file.exists()
+            && !backup.exists()
+            && let Some(converted) = file_name
+                .is_none()
+                .then(|| Self::read_other_format(data_dir, format))
+                .flatten()
+                .or_else(|| Self::read_legacy_file(legacy_file?, &file))
+        {
+            return Some(Self {
+                kv: converted,
+                dirty: true,
+                writer: Self::writer(lock.as_ref(), file, format, compression, true),
+                _lock: lock,
+                #[cfg(feature = "storage-compression")]
+                compression_threshold: None,
+            });
+        }
+
+        let mut file_valid = true;
+        let initial_data = if let Ok(data) = Self::read_file(&file, format) {
+            data
+        } else {
+            file_valid = false;
+            log::warn!(
+                "Restoring the application state from the backup {}",
+                backup.display()
+            );
+            Self::read_file(&backup, format).ok()?
+        };
+
+        Some(Self {
+            kv: initial_data.unwrap_or_default(),
+            dirty: false,
+            writer: Self::writer(lock.as_ref(), file, format, compression, file_valid),
+            _lock: lock,
+            #[cfg(feature = "storage-compression")]
+            compression_threshold: None,
+        })
+    }
+
+    /// Compresses values that are longer than the threshold in bytes when they are set.
+    /// Values that were stored before are not compressed until they are set again.
+    #[cfg(feature = "storage-compression")]
+    pub const fn set_compression_threshold(&mut self, compression_threshold: usize) {
+        self.compression_threshold = Some(compression_threshold);
+    }
+
+    /// Reads the application state stored in another format.
+    /// It is written in the configured format on the next flush, the file of the other format is kept.
+    fn read_other_format(
+        data_dir: &std::path::Path,
+        format: StorageFormat,
+    ) -> Option<std::collections::HashMap<String, String>> {
+        StorageFormat::ALL
+            .iter()
+            .filter(|other| **other != format)
+            .find_map(|other| {
+                let other_file = data_dir.join(other.file_name());
+                let data = Self::read_file(&other_file, *other).ok()??;
+                log::info!(
+                    "The storage format was changed to {format:?}, converting the application state in {}",
+                    other_file.display()
+                );
+                Some(data)
+            })
+    }
+
+    /// Reads the storage file eframe used before the app used the backend selector.
+    /// Unreadable files are skipped, the error is logged.
+    fn read_legacy_file(
+        legacy_file: &std::path::Path,
+        file: &std::path::Path,
+    ) -> Option<std::collections::HashMap<String, String>> {
+        if legacy_file == file {
+            return None;
+        }
+
+        let data = Self::read_file(legacy_file, StorageFormat::Ron).ok()??;
+        log::info!(
+            "Importing the application state stored by eframe in {}",
+            legacy_file.display()
+        );
+        Some(data)
+    }
+
+    /// Creates the writer of the storage. Nothing is written if the lock is not held.
+    fn writer(
+        lock: Option<&std::fs::File>,
+        file: std::path::PathBuf,
+        format: StorageFormat,
+        compression: StorageCompression,
+        file_valid: bool,
+    ) -> Writer {
+        if lock.is_none() {
+            return Writer::ReadOnly;
+        }
+
+        Self::start_writer(FileWriter {
+            file,
+            format,
+            compression,
+            file_valid,
+        })
+    }
+
+    /// Starts the writer thread. If that fails the file is written synchronously.
+    fn start_writer(file_writer: FileWriter) -> Writer {
+        let queue = std::sync::Arc::new(WriterQueue::default());
+        let thread_queue = std::sync::Arc::clone(&queue);
+        let thread_writer = file_writer.clone();
+
+        match std::thread::Builder::new()
+            .name("egui_backend_selector storage".to_string())
+            .spawn(move || thread_queue.run(thread_writer))
+        {
+            Ok(thread) => Writer::Thread {
+                queue,
+                thread: Some(thread),
+            },
+            Err(e) => {
+                log::warn!(
+                    "Failed to start the storage writer thread, the application state is written synchronously. err={e}"
+                );
+                Writer::Sync(file_writer)
+            }
+        }
+    }
+
+    /// Reads the key value map from the file.
+    /// Returns Ok(None) if the file does not exist and Err if it could not be read.
+    fn read_file(
+        file: &std::path::Path,
+        format: StorageFormat,
+    ) -> Result<Option<std::collections::HashMap<String, String>>, ()> {
+        if !file.exists() {
+            return Ok(None);
+        }
+
+        let data = std::fs::read(file).map_err(|e| {
+            log::error!(
+                "Failed to read application state. Could not read file {} err={e}",
+                file.display()
+            );
+        })?;
+
+        StorageCompression::decompress(data)
+            .and_then(|data| format.read(data.as_slice()))
+            .map(Some)
+            .map_err(|e| {
+                log::error!(
+                    "Failed to read application state. File contains invalid data {} err={e}",
+                    file.display()
+                );
+            })
+    }
+}
+
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+impl Storage for KVStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        let value = self.kv.get(key)?;
+        value
+            .strip_prefix(COMPRESSED_PREFIX)
+            .map_or_else(|| Some(value.clone()), decompress_value)
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        #[cfg(feature = "storage-compression")]
+        let value = match self.compression_threshold {
+            //Compressing small values would make them larger because of the base64 encoding.
+            Some(threshold) if value.len() > threshold => compress_value(&value)
+                .filter(|compressed| compressed.len() < value.len())
+                .unwrap_or(value),
+            _ => value,
+        };
+
+        self.kv.insert(key.to_string(), value);
+        self.dirty = true;
+    }
+
+    fn flush(&mut self) {
+        if !self.is_dirty() {
+            return;
+        }
+
+        match &mut self.writer {
+            //Another instance owns the storage, we only have read access.
+            Writer::ReadOnly => return,
+            Writer::Sync(writer) => {
+                if !writer.write(&self.kv) {
+                    return;
+                }
+            }
+            Writer::Thread { queue, .. } => queue.push(self.kv.clone()),
+        }
+
+        self.dirty = false;
+    }
+}
+
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+impl Drop for KVStorage {
+    fn drop(&mut self) {
+        if let Writer::Thread { queue, thread } = &mut self.writer {
+            queue.close();
+            if let Some(thread) = thread.take()
+                && thread.join().is_err()
+            {
+                log::error!(
+                    "The storage writer thread panicked, the application state may not be saved."
+                );
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "persistence", not(target_arch = "wasm32")))]
+impl StorageExt for KVStorage {
+    fn keys(&self) -> Vec<String> {
+        self.kv.keys().cloned().collect()
+    }
+
+    fn clear(&mut self) {
+        self.kv.clear();
+        self.dirty = true;
+    }
+
+    fn wait_for_flush(&self) {
+        if let Writer::Thread { queue, .. } = &self.writer {
+            queue.drain();
+        }
+    }
+
+    fn is_dirty(&self) -> bool {
+        //A state the writer thread failed to write is still not on disk.
+        self.dirty || matches!(&self.writer, Writer::Thread { queue, .. } if queue.write_failed())
+    }
+}
+
+#[cfg(all(test, feature = "persistence", not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    /// Returns an empty directory for the test.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "egui_backend_selector_{name}_{}",
+            std::process::id()
+        ));
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("failed to create the test directory");
+        dir
+    }
+
+    /// Opens the storage file in the directory.
+    fn open(dir: &Path, format: StorageFormat) -> KVStorage {
+        KVStorage::new(dir, None, format, StorageCompression::None, None)
+            .expect("failed to open the storage")
+    }
+
+    /// Sets the values, flushes and waits until they are written.
+    fn write(storage: &mut KVStorage, values: &[(&str, &str)]) {
+        for (key, value) in values {
+            storage.set_string(key, (*value).to_string());
+        }
+        storage.flush();
+        storage.wait_for_flush();
+    }
+
+    /// Keys and values that are hard to escape in RON and JSON.
+    const UNUSUAL_VALUES: &[(&str, &str)] = &[
+        ("größe", "äöü ß 日本語 🦀"),
+        ("multi\nline key", "first line\nsecond line\r\nthird line\n"),
+        ("quotes", "\"double\" 'single' \\ backslash"),
+        (
+            "ron syntax",
+            "(a: [1, 2], b: {\"c\": Some(3)}) // not a comment",
+        ),
+        ("whitespace", " \t leading and trailing\t "),
+        ("", "empty key"),
+        ("empty value", ""),
+    ];
+
+    /// Reads back every value written in the format.
+    fn assert_round_trip(format: StorageFormat) {
+        let dir = test_dir(&format!("round_trip_{format:?}"));
+        let mut storage = open(&dir, format);
+        write(&mut storage, UNUSUAL_VALUES);
+        drop(storage);
+
+        let storage = open(&dir, format);
+        for (key, value) in UNUSUAL_VALUES {
+            assert_eq!(storage.get_string(key).as_deref(), Some(*value), "{key:?}");
+        }
+        assert_eq!(storage.keys().len(), UNUSUAL_VALUES.len());
+        drop(storage);
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// RON keeps non-ASCII keys and multi-line values.
+    #[test]
+    fn ron_round_trip() {
+        assert_round_trip(StorageFormat::Ron);
+    }
+
+    /// JSON keeps non-ASCII keys and multi-line values.
+    #[cfg(feature = "storage-json")]
+    #[test]
+    fn json_round_trip() {
+        assert_round_trip(StorageFormat::Json);
+    }
+
+    /// Changing the format converts the values of the other format.
+    #[cfg(feature = "storage-json")]
+    #[test]
+    fn format_conversion_round_trip() {
+        let dir = test_dir("format_conversion");
+        let mut storage = open(&dir, StorageFormat::Ron);
+        write(&mut storage, UNUSUAL_VALUES);
+        drop(storage);
+
+        let mut storage = open(&dir, StorageFormat::Json);
+        storage.flush();
+        storage.wait_for_flush();
+        drop(storage);
+        assert!(dir.join(StorageFormat::Json.file_name()).exists());
+
+        let storage = open(&dir, StorageFormat::Json);
+        for (key, value) in UNUSUAL_VALUES {
+            assert_eq!(storage.get_string(key).as_deref(), Some(*value), "{key:?}");
+        }
+        drop(storage);
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Values right below, at and above the threshold are read back unchanged,
+    /// and only the values above it are stored compressed.
+    #[cfg(feature = "storage-compression")]
+    #[test]
+    fn compression_threshold_round_trip() {
+        const THRESHOLD: usize = 64;
+        let compressible = |len: usize| "ab".repeat(len).chars().take(len).collect::<String>();
+        let values = [
+            ("below", compressible(THRESHOLD - 1), false),
+            ("at", compressible(THRESHOLD), false),
+            ("above", compressible(THRESHOLD + 1), true),
+            (
+                "nul and control characters",
+                format!("\0\u{1}\u{7f}{}\0", compressible(100)),
+                true,
+            ),
+            //Only values that start with the whole prefix are decompressed.
+            (
+                "prefix lookalike",
+                "\0egui_backend_selector".to_string(),
+                false,
+            ),
+            //Compressing this makes it larger, so it is kept as it is.
+            (
+                "incompressible",
+                (0..u32::try_from(THRESHOLD * 2).expect("threshold too large"))
+                    .map(|i| {
+                        char::from_u32(0x4e00 + i.wrapping_mul(2_654_435_761) % 0x5000)
+                            .expect("invalid char")
+                    })
+                    .collect(),
+                false,
+            ),
+        ];
+
+        let dir = test_dir("compression_threshold");
+        let mut storage = open(&dir, StorageFormat::Ron);
+        storage.set_compression_threshold(THRESHOLD);
+        for (key, value, compressed) in &values {
+            storage.set_string(key, value.clone());
+            assert_eq!(
+                storage.kv[*key].starts_with(COMPRESSED_PREFIX),
+                *compressed,
+                "{key}"
+            );
+        }
+        storage.flush();
+        storage.wait_for_flush();
+        drop(storage);
+
+        //Reading does not depend on the threshold.
+        let storage = open(&dir, StorageFormat::Ron);
+        for (key, value, _) in &values {
+            assert_eq!(storage.get_string(key).as_ref(), Some(value), "{key}");
+        }
+        drop(storage);
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Corrupted compressed values are treated as missing.
+    #[cfg(feature = "storage-compression")]
+    #[test]
+    fn corrupted_compressed_value_is_missing() {
+        let dir = test_dir("corrupted_value");
+        let mut storage = open(&dir, StorageFormat::Ron);
+        storage.kv.insert(
+            "base64".to_string(),
+            format!("{COMPRESSED_PREFIX}not base64!"),
+        );
+        storage
+            .kv
+            .insert("deflate".to_string(), format!("{COMPRESSED_PREFIX}AAAA"));
+        assert_eq!(storage.get_string("base64"), None);
+        assert_eq!(storage.get_string("deflate"), None);
+        drop(storage);
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Compressed values survive the compression of the whole file.
+    #[cfg(all(feature = "storage-compression", feature = "compression"))]
+    #[test]
+    fn value_and_file_compression_round_trip() {
+        for compression in [StorageCompression::Deflate, StorageCompression::Zstd] {
+            let dir = test_dir(&format!("value_and_file_compression_{compression:?}"));
+            let open = || {
+                KVStorage::new(&dir, None, StorageFormat::Ron, compression, None)
+                    .expect("failed to open the storage")
+            };
+            let value = "large value\n".repeat(100);
+
+            let mut storage = open();
+            storage.set_compression_threshold(16);
+            write(&mut storage, &[("large", &value), ("small", "small")]);
+            drop(storage);
+
+            let storage = open();
+            assert_eq!(storage.get_string("large"), Some(value));
+            assert_eq!(storage.get_string("small").as_deref(), Some("small"));
+            drop(storage);
+            _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    /// A file truncated by an interrupted in-place write is restored from the backup,
+    /// and the truncated file does not replace the backup on the next flush.
+    #[test]
+    fn truncated_file_is_restored_from_backup() {
+        let dir = test_dir("truncated_file");
+        let file = dir.join(StorageFormat::Ron.file_name());
+        let backup = with_suffix(&file, ".bak");
+
+        let mut storage = open(&dir, StorageFormat::Ron);
+        write(&mut storage, &[("key", "first"), ("other", "kept")]);
+        write(&mut storage, &[("key", "second")]);
+        drop(storage);
+        assert!(backup.exists());
+
+        let content = std::fs::read(&file).expect("failed to read the file");
+        std::fs::write(&file, &content[..content.len() / 2]).expect("failed to truncate the file");
+
+        let mut storage = open(&dir, StorageFormat::Ron);
+        assert_eq!(storage.get_string("key").as_deref(), Some("first"));
+        assert_eq!(storage.get_string("other").as_deref(), Some("kept"));
+
+        write(&mut storage, &[("key", "third")]);
+        drop(storage);
+
+        let backup_storage = KVStorage::read_file(&backup, StorageFormat::Ron)
+            .expect("the backup was replaced by the truncated file")
+            .expect("the backup is missing");
+        assert_eq!(backup_storage.get("key").map(String::as_str), Some("first"));
+
+        let storage = open(&dir, StorageFormat::Ron);
+        assert_eq!(storage.get_string("key").as_deref(), Some("third"));
+        drop(storage);
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// A file and backup that are both unreadable do not open a storage that would overwrite them.
+    #[test]
+    fn unreadable_file_and_backup_are_not_opened() {
+        let dir = test_dir("unreadable_file");
+        let file = dir.join(StorageFormat::Ron.file_name());
+        std::fs::write(&file, "{\"key\": ").expect("failed to write the file");
+        std::fs::write(with_suffix(&file, ".bak"), "").expect("failed to write the backup");
+
+        assert!(
+            KVStorage::new(
+                &dir,
+                None,
+                StorageFormat::Ron,
+                StorageCompression::None,
+                None
+            )
+            .is_none()
+        );
+        _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Writes a failed flush again, even though nothing changed since.
+    #[test]
+    fn failed_write_stays_dirty() {
+        let dir = test_dir("failed_write");
+        let file = dir.join(StorageFormat::Ron.file_name());
+        //The temporary file cannot be created while a directory has its name.
+        let temp_file = with_suffix(&file, ".tmp");
+        std::fs::create_dir(&temp_file).expect("failed to create the directory");
+
+        let mut storage = open(&dir, StorageFormat::Ron);
+        write(&mut storage, &[("key", "value")]);
+        assert!(storage.is_dirty());
+        assert!(!file.exists());
+
+        std::fs::remove_dir(&temp_file).expect("failed to remove the directory");
+        storage.flush();
+        storage.wait_for_flush();
+        assert!(!storage.is_dirty());
+        drop(storage);
+
+        let storage = open(&dir, StorageFormat::Ron);
+        assert_eq!(storage.get_string("key").as_deref(), Some("value"));
+        drop(storage);
+        _ = std::fs::remove_dir_all(&dir);
+    }
+}
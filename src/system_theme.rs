@@ -0,0 +1,45 @@
+use egui::Context;
+
+/// The dark or light theme preferred by the user in the settings of the os.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum SystemTheme {
+    /// The user prefers a dark theme.
+    Dark,
+
+    /// The user prefers a light theme.
+    Light,
+
+    /// The os supports a theme preference, but the user did not choose one.
+    Unknown,
+}
+
+impl SystemTheme {
+    /// Returns the egui theme. None if the theme is unknown.
+    #[must_use]
+    pub const fn egui_theme(self) -> Option<egui::Theme> {
+        match self {
+            Self::Dark => Some(egui::Theme::Dark),
+            Self::Light => Some(egui::Theme::Light),
+            Self::Unknown => None,
+        }
+    }
+}
+
+/// Queries the theme preference of the os.
+/// With the `system-theme` feature the os is queried directly, otherwise the theme reported by the backend is used.
+/// Returns None if neither is able to tell.
+pub fn detect_system_theme(ctx: &Context) -> Option<SystemTheme> {
+    #[cfg(all(feature = "system-theme", not(target_arch = "wasm32")))]
+    match dark_light::detect() {
+        Ok(dark_light::Mode::Dark) => return Some(SystemTheme::Dark),
+        Ok(dark_light::Mode::Light) => return Some(SystemTheme::Light),
+        Ok(dark_light::Mode::Unspecified) => return Some(SystemTheme::Unknown),
+        Err(e) => log::debug!("Failed to detect the theme of the os err={e}"),
+    }
+
+    match ctx.system_theme()? {
+        egui::Theme::Dark => Some(SystemTheme::Dark),
+        egui::Theme::Light => Some(SystemTheme::Light),
+    }
+}
@@ -0,0 +1,1579 @@
+use crate::frame_stats::{FrameTimings, show_frame_stats};
+use crate::icon::icon_data;
+use crate::monitor::current_monitor;
+use crate::report::{report, set_selection_report};
+use crate::system_theme::detect_system_theme;
+use crate::{
+    BackendCapabilities, BackendProbeResult, BackendSelectionReason, BackendSelectionReport,
+    BackendSelectorError, FrameStats, IconError, MonitorInfo, OverwriteError, ParseBackendError,
+    StorageExt, StorageFormat, StorageMigration, SystemTheme, selection_report,
+};
+use eframe::egui::Context;
+use eframe::{Frame, IntegrationInfo, Storage, WebOptions};
+use std::any::Any;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
+use wasm_bindgen::JsCast;
+
+/// Contains one element for each backend supported by the backend selector.
+/// On the web only `Web` is ever selected, the other variants exist so that the same code compiles on all targets.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    SoftwareBackend,
+    Eframe,
+    Web,
+
+    /// A backend registered with `BackendConfiguration::register_custom_backend`, identified by its id.
+    CustomBackend(u32),
+}
+
+impl Backend {
+    /// All backends, the only one that is used on the web comes first.
+    pub const ALL: [Self; 3] = [Self::Web, Self::Eframe, Self::SoftwareBackend];
+
+    /// Returns the name of the backend. Parsing the name with `FromStr` returns the backend again.
+    /// For custom backends this is "custom", the name returned by `Display` includes the id.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::SoftwareBackend => "software-backend",
+            Self::Eframe => "eframe",
+            Self::Web => "web",
+            Self::CustomBackend(_) => "custom",
+        }
+    }
+
+    /// Returns true if this build can launch the backend on this platform.
+    /// On the web this is only true for `Web`.
+    #[must_use]
+    pub fn is_available(self) -> bool {
+        available_backends().contains(&self)
+    }
+}
+
+/// Returns the backends this build can launch on this platform.
+/// On the web this is only `Web`.
+#[must_use]
+pub const fn available_backends() -> &'static [Backend] {
+    &[Backend::Web]
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CustomBackend(id) => write!(f, "{}-{id}", self.as_str()),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = ParseBackendError;
+
+    /// Parses the name of a backend, ignoring case.
+    /// Accepts the names returned by `as_str`, "software" for the software backend
+    /// and "custom-<id>" for custom backends.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("software") {
+            return Ok(Self::SoftwareBackend);
+        }
+
+        if let Some((prefix, id)) = s.split_once('-')
+            && prefix.eq_ignore_ascii_case(Self::CustomBackend(0).as_str())
+            && let Ok(id) = id.parse()
+        {
+            return Ok(Self::CustomBackend(id));
+        }
+
+        Self::ALL
+            .into_iter()
+            .find(|backend| s.eq_ignore_ascii_case(backend.as_str()))
+            .ok_or_else(|| ParseBackendError(s.to_string()))
+    }
+}
+
+/// Element id of the canvas that is used if no other id is configured.
+const DEFAULT_CANVAS_ID: &str = "the_canvas_id";
+
+/// Static state, true once the app was launched.
+static LAUNCHED: AtomicBool = AtomicBool::new(false);
+
+/// Overwrites the selected backend.
+/// This has no effect on the web, as there is only one backend.
+///
+/// # Errors
+/// * If the application is running.
+/// * If the backend is not `Backend::Web`, see `available_backends`.
+pub fn overwrite_backend(backend: Backend) -> Result<(), OverwriteError> {
+    if !backend.is_available() {
+        return Err(OverwriteError::Unavailable(backend));
+    }
+
+    if is_launched() {
+        return Err(OverwriteError::AlreadyLaunched);
+    }
+
+    log::debug!("Ignoring overwrite of the backend with {backend:?}, the web only has one backend");
+    Ok(())
+}
+
+/// Returns true if the application was already launched.
+pub fn is_launched() -> bool {
+    LAUNCHED.load(Relaxed)
+}
+
+/// The function returns the backend selected to be used for egui.
+/// On the web this is always `Backend::Web`.
+#[must_use]
+#[allow(clippy::unnecessary_wraps)]
+pub const fn get_backend() -> Option<Backend> {
+    Some(Backend::Web)
+}
+
+/// Returns the result of the checks for every backend.
+/// On the web there is only one backend, which is always available.
+#[must_use]
+pub fn probe_backends() -> Vec<BackendProbeResult> {
+    vec![BackendProbeResult {
+        backend: Backend::Web,
+        available: true,
+        reason: BackendSelectionReason::Default,
+        detail: String::new(),
+    }]
+}
+
+/// Returns how long the backend detection took.
+/// There is no detection on the web, so this always returns None.
+#[must_use]
+pub const fn backend_detection_duration() -> Option<std::time::Duration> {
+    None
+}
+
+/// Determines the backend without selecting it.
+/// On the web this is always `Backend::Web`.
+#[must_use]
+#[allow(clippy::unnecessary_wraps)]
+pub const fn probe_backend() -> Option<Backend> {
+    Some(Backend::Web)
+}
+
+/// Determines the backend and the reason for it without selecting it.
+/// On the web this is always `Backend::Web`.
+#[must_use]
+#[allow(clippy::unnecessary_wraps)]
+pub const fn probe_backend_with_reason() -> Option<(Backend, BackendSelectionReason)> {
+    Some((Backend::Web, BackendSelectionReason::Default))
+}
+
+/// There are no probe subprocesses on the web, this does nothing.
+pub const fn handle_probe_subprocess() {}
+
+/// Probes are not run on the web, as there is only one backend.
+pub fn register_probe(probe: impl Fn() -> Option<Backend> + Send + 'static) {
+    _ = probe;
+}
+
+/// There is no crash marker on the web, this does nothing.
+pub const fn clear_crash_marker(app_name: &str, backend_configuration: &BackendConfiguration) {
+    _ = (app_name, backend_configuration);
+}
+
+/// Enum that contains the backend-specific context.
+/// On the web, this is always eframe. The other variants are never constructed,
+/// they exist so that matches on the interop compile on all targets.
+#[non_exhaustive]
+pub enum BackendInterop<'a> {
+    SoftwareBackend(SoftwareBackendInterop<'a>),
+    Eframe(EframeInterop<'a>),
+
+    /// Passed to the app by a backend registered with `BackendConfiguration::register_custom_backend`.
+    Custom(&'a mut CustomBackendInterop),
+}
+
+impl<'a> BackendInterop<'a> {
+    /// Returns the eframe interop, which is the only one that is constructed on the web.
+    const fn eframe(&self) -> &EframeInterop<'a> {
+        match self {
+            BackendInterop::Eframe(efr) => efr,
+            BackendInterop::SoftwareBackend(swb) => match swb.never {},
+            BackendInterop::Custom(custom) => match custom.never {},
+        }
+    }
+
+    /// Returns the eframe interop, which is the only one that is constructed on the web.
+    const fn eframe_mut(&mut self) -> &mut EframeInterop<'a> {
+        match self {
+            BackendInterop::Eframe(efr) => efr,
+            BackendInterop::SoftwareBackend(swb) => match swb.never {},
+            BackendInterop::Custom(custom) => match custom.never {},
+        }
+    }
+}
+
+/// State of the interop that is kept between frames.
+#[derive(Default)]
+struct InteropState {
+    /// The context of the frame, returned by `egui_context`.
+    ctx: Context,
+
+    /// The cursor last set by calling `set_cursor`.
+    cursor: egui::CursorIcon,
+
+    /// The cursor was set during the current frame.
+    cursor_set: bool,
+
+    /// Is the canvas currently fullscreen? None if eframe does not report it.
+    fullscreen: Option<bool>,
+
+    /// The fullscreen state requested by calling `set_fullscreen` during the current frame.
+    requested_fullscreen: Option<bool>,
+
+    /// The scale factor set by calling `set_scale_factor` during the current frame.
+    requested_scale_factor: Option<f32>,
+
+    /// The zoom factor set by calling `set_zoom_factor` during the current frame.
+    requested_zoom_factor: Option<f32>,
+
+    /// The monitor the canvas is currently on, updated before each frame.
+    monitor: Option<MonitorInfo>,
+
+    /// A warning was logged because a paint callback could not be executed.
+    paint_callback_warned: bool,
+
+    /// Timings of the recent frames, returned by `frame_stats`.
+    frame_timings: FrameTimings,
+
+    /// The area of the text cursor set by calling `set_ime_cursor_area` during the current frame.
+    ime_cursor_area: Option<egui::Rect>,
+
+    /// A screenshot was requested by calling `request_screenshot` during the current frame.
+    screenshot_requested: bool,
+
+    /// The last screenshot eframe delivered, until it is taken by calling `take_screenshot`.
+    screenshot: Option<std::sync::Arc<egui::ColorImage>>,
+
+    /// Text that should be copied to the clipboard once `update` returns.
+    #[cfg(feature = "clipboard")]
+    copied_text: Option<String>,
+}
+
+/// The software backend is not available on the web, this is never constructed.
+pub struct SoftwareBackendInterop<'a> {
+    /// Makes the interop impossible to construct.
+    never: std::convert::Infallible,
+
+    /// Keeps the lifetime of the native interop.
+    lifetime: std::marker::PhantomData<&'a mut ()>,
+}
+
+/// Custom backends cannot be launched on the web, this is never constructed.
+pub struct CustomBackendInterop {
+    /// Makes the interop impossible to construct.
+    never: std::convert::Infallible,
+}
+
+impl CustomBackendInterop {
+    /// Returns the id the backend was registered with.
+    #[must_use]
+    pub const fn id(&self) -> u32 {
+        match self.never {}
+    }
+
+    /// Returns the backend-specific data.
+    #[must_use]
+    pub const fn data(&self) -> &dyn Any {
+        match self.never {}
+    }
+
+    /// Returns the backend-specific data.
+    #[must_use]
+    pub const fn data_mut(&mut self) -> &mut dyn Any {
+        match self.never {}
+    }
+}
+
+/// Wrapper for the eframe `Frame`
+pub struct EframeInterop<'a> {
+    /// The reference to the actual eframe frame.
+    frame: &'a mut Frame,
+
+    /// State kept between frames.
+    state: &'a mut InteropState,
+}
+
+impl Deref for EframeInterop<'_> {
+    type Target = Frame;
+
+    fn deref(&self) -> &Self::Target {
+        self.frame
+    }
+}
+
+impl DerefMut for EframeInterop<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.frame
+    }
+}
+
+impl BackendInterop<'_> {
+    #[must_use]
+    pub const fn backend(&self) -> Backend {
+        Backend::Web
+    }
+
+    /// Returns the egui context of the frame, e.g. to open secondary windows with
+    /// `egui::Context::show_viewport_immediate` or `egui::Context::show_viewport_deferred`.
+    #[must_use]
+    pub const fn egui_context(&self) -> &Context {
+        &self.eframe().state.ctx
+    }
+
+    /// Returns the dark or light theme preferred by the browser, as reported by eframe.
+    /// Returns None if the browser does not report it.
+    #[must_use]
+    pub fn system_theme(&self) -> Option<SystemTheme> {
+        detect_system_theme(self.egui_context())
+    }
+
+    /// eframe shows secondary viewports embedded in the canvas on the web, this always returns false.
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub const fn supports_multiple_viewports(&self) -> bool {
+        false
+    }
+
+    /// Returns a human readable name of the backend.
+    /// Use `Backend::as_str` for the name that is accepted by `Backend::from_str`.
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub const fn backend_name(&self) -> &'static str {
+        "web"
+    }
+
+    #[must_use]
+    pub const fn is_web(&self) -> bool {
+        true
+    }
+
+    #[must_use]
+    pub fn into(&self) -> &IntegrationInfo {
+        self.eframe().info()
+    }
+
+    /// Returns how long the last frame took on the CPU.
+    /// Returns None before the first frame or if the backend does not measure it.
+    #[must_use]
+    pub fn last_frame_time(&self) -> Option<std::time::Duration> {
+        self.into()
+            .cpu_usage
+            .and_then(|secs| std::time::Duration::try_from_secs_f32(secs).ok())
+    }
+
+    /// Returns the timing statistics of the recent frames.
+    /// They are recorded before every frame, including frames where the app does nothing.
+    #[must_use]
+    pub fn frame_stats(&self) -> FrameStats {
+        self.eframe().state.frame_timings.stats()
+    }
+
+    /// Returns the frames per second the app could render if every frame took as long as the last frame.
+    /// This is an upper bound, as the time spent waiting for the display is not included.
+    #[must_use]
+    pub fn estimated_fps(&self) -> Option<f32> {
+        self.last_frame_time()
+            .filter(|frame_time| !frame_time.is_zero())
+            .map(|frame_time| 1.0 / frame_time.as_secs_f32())
+    }
+
+    #[must_use]
+    pub fn storage(&self) -> Option<&dyn Storage> {
+        self.eframe().storage()
+    }
+
+    pub fn storage_mut(&mut self) -> Option<&mut (dyn Storage + 'static)> {
+        self.eframe_mut().storage_mut()
+    }
+
+    /// eframe stores the data in the local storage of the browser, there is no directory.
+    #[must_use]
+    pub const fn storage_path(&self) -> Option<&std::path::Path> {
+        None
+    }
+
+    /// Returns which features are available with the enabled feature flags.
+    /// There are no native window handles and dropped files have no path on the web.
+    #[must_use]
+    pub fn capabilities(&self) -> BackendCapabilities {
+        #[allow(unused_mut)]
+        let mut capabilities = BackendCapabilities {
+            has_glow: false,
+            has_wgpu: false,
+            has_persistent_storage: self.storage().is_some(),
+            has_raw_window_handle: false,
+            has_clipboard: cfg!(feature = "clipboard"),
+            has_file_drop: false,
+        };
+
+        #[cfg(feature = "glow")]
+        {
+            let efr = self.eframe();
+            capabilities.has_glow = efr.gl().is_some();
+        }
+
+        #[cfg(feature = "wgpu")]
+        {
+            capabilities.has_wgpu = self.wgpu_render_state().is_some();
+        }
+
+        capabilities
+    }
+
+    /// Returns the storage with the additional functionality of `StorageExt`.
+    /// Always returns None on the web, as eframe manages its own storage.
+    #[allow(clippy::unused_self)]
+    pub const fn storage_ext(&mut self) -> Option<&mut (dyn StorageExt + 'static)> {
+        None
+    }
+
+    #[cfg(feature = "glow")]
+    pub fn gl(&mut self) -> Option<&std::sync::Arc<eframe::glow::Context>> {
+        self.eframe_mut().gl()
+    }
+
+    /// Returns true if `register_native_glow_texture` can register opengl textures.
+    /// eframe cannot register native textures on the web, so this always returns false.
+    #[cfg(feature = "glow")]
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub const fn supports_native_gl_textures(&self) -> bool {
+        false
+    }
+
+    /// eframe cannot register native opengl textures on the web.
+    /// This always logs a warning and returns None.
+    #[cfg(feature = "glow")]
+    #[allow(clippy::unused_self)]
+    pub fn register_native_glow_texture(
+        &mut self,
+        native: eframe::glow::Texture,
+    ) -> Option<egui::TextureId> {
+        _ = native;
+        log::warn!("eframe cannot register native opengl textures on the web");
+        None
+    }
+
+    /// Returns true if the renderer executes paint callbacks, see `add_paint_callback`.
+    /// eframe executes glow callbacks with the glow renderer and wgpu callbacks with the wgpu renderer.
+    #[must_use]
+    pub fn supports_paint_callbacks(&self) -> bool {
+        let efr = self.eframe();
+
+        #[allow(unused_mut)]
+        let mut supported = false;
+
+        #[cfg(feature = "glow")]
+        {
+            supported |= efr.frame.gl().is_some();
+        }
+
+        #[cfg(feature = "wgpu")]
+        {
+            supported |= efr.frame.wgpu_render_state().is_some();
+        }
+
+        _ = efr;
+        supported
+    }
+
+    /// Returns true if the renderer executes this paint callback.
+    fn supports_paint_callback(&self, callback: &egui::PaintCallback) -> bool {
+        let efr = self.eframe();
+
+        #[cfg(feature = "glow")]
+        if efr.frame.gl().is_some() && callback.callback.is::<eframe::egui_glow::CallbackFn>() {
+            return true;
+        }
+
+        #[cfg(feature = "wgpu")]
+        if efr.frame.wgpu_render_state().is_some()
+            && callback.callback.is::<eframe::egui_wgpu::Callback>()
+        {
+            return true;
+        }
+
+        _ = (efr, callback);
+        false
+    }
+
+    /// Adds the paint callback to the painter if the renderer executes it, otherwise `fallback` is called
+    /// with the painter and the rect of the callback to paint a replacement with egui shapes.
+    /// A warning is logged the first time the fallback is used.
+    pub fn add_paint_callback(
+        &mut self,
+        painter: &egui::Painter,
+        callback: egui::PaintCallback,
+        fallback: impl FnOnce(&egui::Painter, egui::Rect),
+    ) {
+        if self.supports_paint_callback(&callback) {
+            painter.add(callback);
+            return;
+        }
+
+        let efr = self.eframe_mut();
+        if !std::mem::replace(&mut efr.state.paint_callback_warned, true) {
+            log::warn!(
+                "The renderer of eframe cannot execute the paint callback, painting the fallback instead"
+            );
+        }
+
+        fallback(painter, callback.rect);
+    }
+
+    /// Uploads an image from cpu memory as a texture egui can paint, this works the same with every backend.
+    ///
+    /// The texture is freed once the last clone of the handle is dropped.
+    /// To stream frames, e.g. from a camera or a video decoder, keep the handle and replace the image
+    /// with `egui::TextureHandle::set` instead of registering a new texture every frame.
+    /// The software backend keeps the image in cpu memory, so uploading it only copies the pixels.
+    pub fn register_texture(
+        &self,
+        name: &str,
+        image: egui::ColorImage,
+        options: egui::TextureOptions,
+    ) -> egui::TextureHandle {
+        self.egui_context().load_texture(name, image, options)
+    }
+
+    /// Returns the wgpu render state if eframe is using the wgpu renderer.
+    #[cfg(feature = "wgpu")]
+    #[must_use]
+    pub fn wgpu_render_state(&self) -> Option<&eframe::egui_wgpu::RenderState> {
+        self.eframe().wgpu_render_state()
+    }
+
+    /// Registers a wgpu texture, so egui can paint it.
+    /// Returns None and logs a warning if eframe is not using the wgpu renderer.
+    #[cfg(feature = "wgpu")]
+    pub fn register_wgpu_texture(
+        &mut self,
+        texture: &eframe::wgpu::Texture,
+    ) -> Option<egui::TextureId> {
+        let Some(render_state) = self.wgpu_render_state() else {
+            log::warn!(
+                "eframe is not using the wgpu renderer, it cannot register native wgpu textures"
+            );
+            return None;
+        };
+
+        Some(render_state.renderer.write().register_native_texture(
+            &render_state.device,
+            &texture.create_view(&eframe::wgpu::TextureViewDescriptor::default()),
+            eframe::wgpu::FilterMode::Linear,
+        ))
+    }
+
+    /// There is no native window on the web, this always returns None.
+    #[cfg(feature = "raw-window-handle")]
+    #[allow(clippy::unused_self)]
+    pub const fn window_handle(&self) -> Option<raw_window_handle::WindowHandle<'_>> {
+        None
+    }
+
+    /// There is no native display on the web, this always returns None.
+    #[cfg(feature = "raw-window-handle")]
+    #[allow(clippy::unused_self)]
+    pub const fn display_handle(&self) -> Option<raw_window_handle::DisplayHandle<'_>> {
+        None
+    }
+
+    /// A web page cannot close itself, this does nothing.
+    pub const fn request_exit(&mut self) {}
+
+    /// Sets the cursor shown for the current frame.
+    /// It is applied through `Context::set_cursor_icon` once `update` returns,
+    /// so it takes precedence over the cursors set by widgets during the frame.
+    pub const fn set_cursor(&mut self, cursor: egui::CursorIcon) {
+        let efr = self.eframe_mut();
+        efr.state.cursor = cursor;
+        efr.state.cursor_set = true;
+    }
+
+    /// Enters or leaves fullscreen once `update` returns.
+    pub const fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.eframe_mut().state.requested_fullscreen = Some(fullscreen);
+    }
+
+    /// Returns true if the canvas is fullscreen.
+    /// Returns false if eframe does not report whether it is fullscreen.
+    #[must_use]
+    pub const fn is_fullscreen(&self) -> bool {
+        matches!(self.eframe().state.fullscreen, Some(true))
+    }
+
+    /// Sets the number of physical pixels per point once `update` returns.
+    /// Values outside the range (0, 10] are ignored.
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        if !is_valid_scale_factor(scale_factor) {
+            log::warn!("Ignoring invalid scale factor {scale_factor}");
+            return;
+        }
+
+        self.eframe_mut().state.requested_scale_factor = Some(scale_factor);
+    }
+
+    /// Sets the zoom factor of egui once `update` returns, the number of physical pixels per point
+    /// becomes the device pixel ratio of the browser multiplied by the zoom factor.
+    /// Values outside the range (0, 10] are ignored.
+    pub fn set_zoom_factor(&mut self, zoom_factor: f32) {
+        if !is_valid_scale_factor(zoom_factor) {
+            log::warn!("Ignoring invalid zoom factor {zoom_factor}");
+            return;
+        }
+
+        self.eframe_mut().state.requested_zoom_factor = Some(zoom_factor);
+    }
+
+    /// Returns the zoom factor of egui, 1.0 unless it was changed.
+    #[must_use]
+    pub fn zoom_factor(&self) -> f32 {
+        self.egui_context().zoom_factor()
+    }
+
+    /// Returns the number of physical pixels per point, this includes the device pixel ratio and the zoom factor.
+    #[must_use]
+    pub fn pixels_per_point(&self) -> f32 {
+        self.egui_context().pixels_per_point()
+    }
+
+    /// The size of the canvas is determined by the page, this does nothing and returns false.
+    pub const fn set_inner_size(&mut self, size: egui::Vec2) -> bool {
+        _ = size;
+        false
+    }
+
+    /// A web page cannot request the attention of the user, this does nothing and returns false.
+    pub const fn request_user_attention(&mut self, attention: egui::UserAttentionType) -> bool {
+        _ = attention;
+        false
+    }
+
+    /// A web page cannot move the browser window, this does nothing and returns false.
+    pub const fn start_window_drag(&mut self) -> bool {
+        false
+    }
+
+    /// The browser always paints the canvas in sync with the display, this always returns true.
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub const fn vsync_enabled(&self) -> bool {
+        true
+    }
+
+    /// The browser always paints the canvas in sync with the display, this logs a warning if vsync is disabled.
+    #[allow(clippy::unused_self)]
+    pub fn set_vsync(&mut self, enabled: bool) {
+        if !enabled {
+            log::warn!(
+                "The browser always paints the canvas in sync with the display, vsync cannot be disabled"
+            );
+        }
+    }
+
+    /// Places the candidate window of the input method next to the area of the text cursor, in points.
+    ///
+    /// This also enables the input method for the frame. It has to be called every frame while text is typed,
+    /// like egui's own text edits do. eframe moves its hidden text input of the browser to the area.
+    pub const fn set_ime_cursor_area(&mut self, rect: egui::Rect) {
+        self.eframe_mut().state.ime_cursor_area = Some(rect);
+    }
+
+    /// Places the candidate window of the input method at the position of the text cursor, in points.
+    /// See `set_ime_cursor_area`.
+    pub const fn set_ime_position(&mut self, position: egui::Pos2) {
+        self.set_ime_cursor_area(egui::Rect::from_min_max(position, position));
+    }
+
+    /// The canvas has no title bar or borders, this does nothing.
+    pub const fn set_decorations(&mut self, decorations: bool) {
+        _ = decorations;
+    }
+
+    /// A web page cannot stay above other windows, this does nothing.
+    pub const fn set_always_on_top(&mut self, always_on_top: bool) {
+        _ = always_on_top;
+    }
+
+    /// A web page cannot minimize the browser window, this does nothing.
+    pub const fn minimize(&mut self) {}
+
+    /// A web page cannot maximize the browser window, this does nothing.
+    pub const fn maximize(&mut self) {}
+
+    /// A web page cannot restore the browser window, this does nothing.
+    pub const fn restore(&mut self) {}
+
+    /// The canvas is never maximized, this always returns false. Use `is_fullscreen` instead.
+    #[must_use]
+    pub const fn is_maximized(&self) -> bool {
+        false
+    }
+
+    /// The canvas is never minimized, this always returns false.
+    #[must_use]
+    pub const fn is_minimized(&self) -> bool {
+        false
+    }
+
+    /// The opacity of the canvas is controlled by the CSS of the page, this does nothing.
+    pub const fn set_opacity(&mut self, alpha: f32) {
+        _ = alpha;
+    }
+
+    /// The opacity of the canvas is controlled by the CSS of the page, this always returns 1.0.
+    #[must_use]
+    pub const fn opacity(&self) -> f32 {
+        1.0
+    }
+
+    /// Sets the title of the browser tab.
+    #[allow(clippy::unused_self)]
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+            document.set_title(&title.into());
+        }
+    }
+
+    /// Requests a screenshot of the canvas once `update` returns, it is returned by `take_screenshot`.
+    /// The screenshot is usually available in the next frame. The image is in physical pixels.
+    pub const fn request_screenshot(&mut self) {
+        self.eframe_mut().state.screenshot_requested = true;
+    }
+
+    /// Returns the last screenshot requested by calling `request_screenshot`. None if it was not delivered yet.
+    /// Each screenshot is only returned once.
+    pub const fn take_screenshot(&mut self) -> Option<std::sync::Arc<egui::ColorImage>> {
+        self.eframe_mut().state.screenshot.take()
+    }
+
+    /// Returns the monitor the canvas is currently on, as reported by egui.
+    /// None before egui reported it.
+    #[must_use]
+    pub fn current_monitor(&self) -> Option<MonitorInfo> {
+        self.eframe().state.monitor.clone()
+    }
+
+    /// Returns the cursor last set by calling `set_cursor`.
+    #[must_use]
+    pub const fn get_cursor(&self) -> egui::CursorIcon {
+        self.eframe().state.cursor
+    }
+
+    /// There is only one backend on the web, this does nothing.
+    pub const fn set_preferred_backend(&mut self, backend: Option<Backend>) {
+        _ = backend;
+    }
+
+    /// The clipboard of the browser can only be read asynchronously, so this always returns None on the web.
+    /// Use the paste events of egui instead.
+    #[cfg(feature = "clipboard")]
+    #[allow(clippy::unused_self)]
+    pub const fn clipboard_text(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Puts the text into the clipboard of the browser once `update` returns.
+    #[cfg(feature = "clipboard")]
+    pub fn set_clipboard_text(&mut self, text: impl Into<String>) {
+        self.eframe_mut().state.copied_text = Some(text.into());
+    }
+
+    /// Returns the report of why the current backend was selected.
+    /// This is the same as calling `selection_report`.
+    #[must_use]
+    pub fn selection_report(&self) -> Option<BackendSelectionReport> {
+        selection_report()
+    }
+
+    /// Shows information for debugging: the backend, the frame timings and the renderer of eframe.
+    /// Rows that do not apply to the enabled features are omitted.
+    ///
+    /// This only adds a few labels to the ui, so it is cheap enough to keep in release builds,
+    /// e.g. in an `egui::Window` that is toggled with a shortcut.
+    pub fn show_debug_ui(&mut self, ui: &mut egui::Ui) {
+        let efr = self.eframe_mut();
+
+        #[allow(unused_mut)]
+        let mut rows = vec![("Backend", Backend::Web.to_string())];
+
+        #[cfg(feature = "glow")]
+        if let Some(gl) = efr.frame.gl() {
+            use eframe::glow::HasContext;
+
+            //The context is current while the app is updated.
+            let (renderer, version) = unsafe {
+                (
+                    gl.get_parameter_string(eframe::glow::RENDERER),
+                    gl.get_parameter_string(eframe::glow::VERSION),
+                )
+            };
+            rows.push(("WebGL renderer", renderer));
+            rows.push(("WebGL version", version));
+        }
+
+        #[cfg(feature = "wgpu")]
+        if let Some(render_state) = efr.frame.wgpu_render_state() {
+            let adapter = render_state.adapter.get_info();
+            rows.push(("wgpu adapter", adapter.name));
+            rows.push(("wgpu backend", format!("{:?}", adapter.backend)));
+        }
+
+        let stats = efr.state.frame_timings.stats();
+
+        egui::Grid::new("egui_backend_selector_debug_ui")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (name, value) in rows {
+                    ui.label(name);
+                    ui.label(value);
+                    ui.end_row();
+                }
+
+                show_frame_stats(ui, &stats);
+            });
+    }
+}
+
+/// App traits
+pub trait App {
+    /// The update loop
+    fn update(&mut self, context: &Context, backend: BackendInterop<'_>);
+
+    /// This function is called once when the application exists.
+    /// It is NOT called when using eframe with the wgpu backend.
+    fn on_exit(&mut self) {}
+
+    /// This function is called before `on_exit` and allows you to save state
+    /// It might be called periodically too
+    fn save(&mut self, storage: &mut dyn Storage) {
+        _ = storage;
+    }
+
+    /// This function is called when the user requested to close the window.
+    /// A web page has no window to close, so this is never called on the web.
+    fn on_close_requested(&mut self, context: &Context) -> bool {
+        _ = context;
+        true
+    }
+
+    /// This function is called when files were dropped onto the window.
+    /// Files dropped onto a web page have no path, so this is never called on the web.
+    fn on_file_drop(
+        &mut self,
+        context: &Context,
+        backend: BackendInterop<'_>,
+        paths: Vec<PathBuf>,
+    ) {
+        _ = (context, backend, paths);
+    }
+
+    /// This function is called when the files dragged over the window changed.
+    /// Files dragged over a web page have no path, so this is never called on the web.
+    fn on_file_hover(
+        &mut self,
+        context: &Context,
+        backend: BackendInterop<'_>,
+        paths: Vec<PathBuf>,
+    ) {
+        _ = (context, backend, paths);
+    }
+
+    /// This function is called when the window gained the keyboard focus, right before `update` is called.
+    /// It is not called for the first frame, even if the window is focused then.
+    fn on_focus_gained(&mut self, context: &Context) {
+        _ = context;
+    }
+
+    /// This function is called when the window lost the keyboard focus, right before `update` is called.
+    fn on_focus_lost(&mut self, context: &Context) {
+        _ = context;
+    }
+
+    /// This function is called when the inner size of the window changed, right before `update` is called.
+    /// The new size is in physical pixels. It is not called for the first frame.
+    fn on_resize(&mut self, context: &Context, new_size_in_pixels: egui::Vec2) {
+        _ = (context, new_size_in_pixels);
+    }
+
+    /// This function is called once after the app is created, before the first `update`.
+    /// The context is fully configured at this point, e.g. the restored egui memory and the scale factor are applied,
+    /// so this is the place for one-time setup like fonts, visuals or installing image loaders.
+    fn post_init(&mut self, ctx: &Context) {
+        _ = ctx;
+    }
+
+    /// Returns how often the state of the app is saved while it is running, both backends call `save` this often.
+    /// `save` is called once more when the app exits.
+    /// This is called again after every save, so the interval can change while the app is running.
+    /// Intervals shorter than one second are treated as one second.
+    fn auto_save_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(30)
+    }
+
+    /// Called every frame before the input is processed, including frames without events.
+    /// The app can change the input, e.g. to handle global shortcuts or to inject synthetic input for automation.
+    ///
+    /// eframe calls this before the input is passed to egui.
+    /// The software backend does not expose its input before the frame starts, so there it is called at the
+    /// start of the frame instead and the changed events and modifiers replace the ones of the current frame.
+    /// Other changes, like the screen rect, take effect on the software backend only once egui processes them again.
+    fn raw_input_hook(&mut self, ctx: &Context, raw_input: &mut egui::RawInput) {
+        _ = (ctx, raw_input);
+    }
+
+    /// Returns the color the window is cleared with before each frame, in normalized gamma space.
+    /// It is visible behind translucent egui content and, with a transparent viewport, behind the window.
+    /// The default is the same as in eframe.
+    fn clear_color(&self, visuals: &egui::Visuals) -> [f32; 4] {
+        _ = visuals;
+        egui::Color32::from_rgba_unmultiplied(12, 12, 12, 180).to_normalized_gamma_f32()
+    }
+}
+
+/// Size changes of the window smaller than this many pixels are considered floating point noise.
+const RESIZE_EPSILON: f32 = 0.5;
+
+/// Wraps the app for eframe.
+struct AppWrapper<T: App> {
+    /// The actual app.
+    app: T,
+
+    /// State of the interop kept between frames.
+    state: InteropState,
+
+    /// Was the window focused in the previous frame? None before the first frame.
+    focused: Option<bool>,
+
+    /// The inner size of the window in pixels in the previous frame. None before the first frame.
+    size_in_pixels: Option<egui::Vec2>,
+
+    /// The shortest time a frame may take. None if the frame rate is unlimited.
+    min_frame_time: Option<std::time::Duration>,
+}
+
+impl<T: App> eframe::App for AppWrapper<T> {
+    fn auto_save_interval(&self) -> std::time::Duration {
+        self.app
+            .auto_save_interval()
+            .max(std::time::Duration::from_secs(1))
+    }
+
+    fn clear_color(&self, visuals: &egui::Visuals) -> [f32; 4] {
+        self.app.clear_color(visuals)
+    }
+
+    fn raw_input_hook(&mut self, ctx: &Context, raw_input: &mut egui::RawInput) {
+        self.app.raw_input_hook(ctx, raw_input);
+    }
+
+    fn update(&mut self, ctx: &Context, frame: &mut Frame) {
+        self.state
+            .frame_timings
+            .record(frame.info().cpu_usage, ctx.input(|i| i.time));
+        self.state.ctx.clone_from(ctx);
+        self.state.monitor = current_monitor(ctx);
+        self.state.fullscreen = ctx.input(|i| i.viewport().fullscreen);
+        if let Some(screenshot) = ctx.input(|i| {
+            i.raw.events.iter().rev().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(std::sync::Arc::clone(image)),
+                _ => None,
+            })
+        }) {
+            self.state.screenshot = Some(screenshot);
+        }
+
+        let focused = ctx.input(|i| i.focused);
+        match self.focused.replace(focused) {
+            Some(false) if focused => self.app.on_focus_gained(ctx),
+            Some(true) if !focused => self.app.on_focus_lost(ctx),
+            _ => {}
+        }
+
+        let size_in_pixels = ctx.input(|i| i.viewport_rect().size() * i.pixels_per_point());
+        if let Some(previous) = self.size_in_pixels.replace(size_in_pixels)
+            && (previous - size_in_pixels).abs().max_elem() > RESIZE_EPSILON
+        {
+            self.app.on_resize(ctx, size_in_pixels);
+        }
+
+        self.app.update(
+            ctx,
+            BackendInterop::Eframe(EframeInterop {
+                frame,
+                state: &mut self.state,
+            }),
+        );
+
+        if std::mem::take(&mut self.state.cursor_set) {
+            ctx.set_cursor_icon(self.state.cursor);
+        }
+
+        if let Some(fullscreen) = self.state.requested_fullscreen.take() {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(fullscreen));
+        }
+
+        if let Some(scale_factor) = self.state.requested_scale_factor.take() {
+            ctx.set_pixels_per_point(scale_factor);
+        }
+
+        if let Some(zoom_factor) = self.state.requested_zoom_factor.take() {
+            ctx.set_zoom_factor(zoom_factor);
+        }
+
+        if let Some(rect) = self.state.ime_cursor_area.take() {
+            ctx.output_mut(|o| {
+                o.ime = Some(egui::output::IMEOutput {
+                    rect,
+                    cursor_rect: rect,
+                });
+            });
+        }
+
+        if std::mem::take(&mut self.state.screenshot_requested) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
+        }
+
+        #[cfg(feature = "clipboard")]
+        if let Some(text) = self.state.copied_text.take() {
+            ctx.copy_text(text);
+        }
+
+        if let Some(min_frame_time) = self.min_frame_time {
+            ctx.request_repaint_after(min_frame_time);
+        }
+    }
+
+    fn save(&mut self, storage: &mut dyn Storage) {
+        self.app.save(storage);
+    }
+
+    #[cfg(feature = "glow")]
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.app.on_exit();
+    }
+
+    #[cfg(not(feature = "glow"))]
+    fn on_exit(&mut self) {
+        self.app.on_exit();
+    }
+}
+
+/// Configuration of the app on the web.
+/// Most options only matter for the native backends, they are accepted but have no effect here.
+#[derive(Default)]
+pub struct BackendConfiguration {
+    /// Options for eframe.
+    web_options: WebOptions,
+
+    /// Element id of the canvas the app is drawn on. None uses `the_canvas_id`.
+    canvas_id: Option<String>,
+
+    /// Called once the backend is selected, before eframe is started.
+    backend_selected_callback: Option<Box<dyn FnOnce(Backend) + Send>>,
+
+    /// Backends that may be used. None allows all backends.
+    allowed_backends: Option<Vec<Backend>>,
+
+    /// Number of physical pixels per point set before the app is created. None uses the scale factor of the browser.
+    scale_factor: Option<f32>,
+
+    /// The maximum number of frames per second. None is unlimited.
+    max_fps: Option<f32>,
+}
+
+impl BackendConfiguration {
+    /// Sets the element id of the canvas the app is drawn on.
+    /// By default, the canvas with the id `the_canvas_id` is used.
+    #[must_use]
+    pub fn with_canvas_id(mut self, canvas_id: impl Into<String>) -> Self {
+        self.canvas_id = Some(canvas_id.into());
+        self
+    }
+
+    /// There is no opengl probe on the web, this has no effect.
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_probe_cache_path(self, probe_cache_path: Option<PathBuf>) -> Self {
+        _ = probe_cache_path;
+        self
+    }
+
+    /// There is no fallback on the web, this has no effect.
+    #[must_use]
+    pub const fn with_eframe_fallback(self, eframe_fallback: bool) -> Self {
+        _ = eframe_fallback;
+        self
+    }
+
+    /// There is no opengl probe on the web, this has no effect.
+    #[must_use]
+    pub const fn with_isolated_probe(self, isolated_probe: bool) -> Self {
+        _ = isolated_probe;
+        self
+    }
+
+    /// There is no opengl probe on the web, this has no effect.
+    #[must_use]
+    pub const fn with_force_reprobe(self, force_reprobe: bool) -> Self {
+        _ = force_reprobe;
+        self
+    }
+
+    /// There is only one backend on the web, so the function is never called.
+    #[must_use]
+    pub const fn with_backend_override_fn(
+        self,
+        backend_override_fn: fn() -> Option<Backend>,
+    ) -> Self {
+        _ = backend_override_fn;
+        self
+    }
+
+    /// Every browser tab runs its own instance, this has no effect.
+    #[cfg(feature = "single-instance")]
+    #[must_use]
+    pub const fn with_single_instance(self, single_instance: bool) -> Self {
+        _ = single_instance;
+        self
+    }
+
+    /// The event loop of the web never exits, this has no effect.
+    #[must_use]
+    pub const fn with_reselect_on_relaunch(self, reselect_on_relaunch: bool) -> Self {
+        _ = reselect_on_relaunch;
+        self
+    }
+
+    /// There is no opengl probe on the web, this has no effect.
+    #[must_use]
+    pub const fn with_probe_timeout(self, probe_timeout: std::time::Duration) -> Self {
+        _ = probe_timeout;
+        self
+    }
+
+    /// There is no opengl probe on the web, this has no effect.
+    #[must_use]
+    pub const fn with_allow_software_gl(self, allow_software_gl: bool) -> Self {
+        _ = allow_software_gl;
+        self
+    }
+
+    /// The web backend is always used.
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub fn probe_backend(&self) -> Option<BackendSelectionReport> {
+        Some(report(
+            Backend::Web,
+            BackendSelectionReason::Default,
+            String::new(),
+        ))
+    }
+
+    /// There is no platform detection on the web, this has no effect.
+    #[must_use]
+    pub fn with_platform_detector(
+        self,
+        platform_detector: Box<dyn crate::PlatformDetector>,
+    ) -> Self {
+        drop(platform_detector);
+        self
+    }
+
+    /// There is no opengl probe on the web, this has no effect.
+    #[must_use]
+    pub const fn with_minimum_opengl_version(self, major: u8, minor: u8) -> Self {
+        _ = (major, minor);
+        self
+    }
+
+    /// eframe persists the egui memory in the local storage of the browser itself, this has no effect.
+    #[must_use]
+    pub const fn with_persist_egui_memory(self, persist_egui_memory: bool) -> Self {
+        _ = persist_egui_memory;
+        self
+    }
+
+    /// eframe does not persist the zoom factor on the web, this has no effect.
+    #[must_use]
+    pub const fn with_persist_zoom_factor(self, persist_zoom_factor: bool) -> Self {
+        _ = persist_zoom_factor;
+        self
+    }
+
+    /// The browser decides the size of the canvas, this has no effect.
+    #[must_use]
+    pub const fn with_persist_window_geometry(self, persist_window_geometry: bool) -> Self {
+        _ = persist_window_geometry;
+        self
+    }
+
+    /// There is only one backend on the web, this has no effect.
+    #[must_use]
+    pub const fn with_crash_protection(self, crash_protection: bool) -> Self {
+        _ = crash_protection;
+        self
+    }
+
+    /// Sets the number of physical pixels per point, overriding the scale factor of the browser.
+    /// It is set before the app factory is called. `run_app` fails if it is not in the range (0, 10].
+    #[must_use]
+    pub const fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = Some(scale_factor);
+        self
+    }
+
+    /// Limits the number of frames per second, None is unlimited. This is the default.
+    /// eframe is asked to repaint once the shortest frame time elapsed, it still repaints sooner on input.
+    /// Values that are not positive are ignored.
+    #[must_use]
+    pub fn with_max_fps(mut self, max_fps: Option<f32>) -> Self {
+        self.max_fps = max_fps.filter(|max_fps| max_fps.is_finite() && *max_fps > 0.0);
+        self
+    }
+
+    /// The canvas has no title bar or borders, this has no effect.
+    #[must_use]
+    pub const fn with_decorations(self, decorations: bool) -> Self {
+        _ = decorations;
+        self
+    }
+
+    /// A web page cannot stay above other windows, this has no effect.
+    #[must_use]
+    pub const fn with_always_on_top(self, always_on_top: bool) -> Self {
+        _ = always_on_top;
+        self
+    }
+
+    /// The browser always paints the canvas in sync with the display, this has no effect.
+    #[must_use]
+    pub const fn with_vsync(self, vsync: bool) -> Self {
+        _ = vsync;
+        self
+    }
+
+    /// The web backend of eframe has no AccessKit integration, this has no effect.
+    #[must_use]
+    pub const fn with_accessibility(self, accessibility: bool) -> Self {
+        _ = accessibility;
+        self
+    }
+
+    /// eframe follows the theme preferred by the browser, this has no effect.
+    #[must_use]
+    pub const fn with_system_theme(self, system_theme: bool) -> Self {
+        _ = system_theme;
+        self
+    }
+
+    /// The opacity of the canvas is controlled by the CSS of the page, this has no effect.
+    #[must_use]
+    pub const fn with_initial_opacity(self, alpha: f32) -> Self {
+        _ = alpha;
+        self
+    }
+
+    /// The size of the canvas is determined by the page, this has no effect.
+    #[must_use]
+    pub const fn with_maximized(self, maximized: bool) -> Self {
+        _ = maximized;
+        self
+    }
+
+    /// The browser shows the favicon of the page instead, so the icon is only validated.
+    ///
+    /// # Errors
+    /// If the width or height is 0 or if the length of `rgba` is not width * height * 4.
+    pub fn with_icon(self, width: u32, height: u32, rgba: Vec<u8>) -> Result<Self, IconError> {
+        icon_data(width, height, rgba)?;
+        Ok(self)
+    }
+
+    /// eframe writes the local storage of the browser when the app is saved, this has no effect.
+    #[must_use]
+    pub const fn with_storage_flush_interval(
+        self,
+        storage_flush_interval: std::time::Duration,
+    ) -> Self {
+        _ = storage_flush_interval;
+        self
+    }
+
+    /// eframe saves the state with the interval returned by `App::auto_save_interval`, this has no effect.
+    #[must_use]
+    pub const fn with_auto_save_interval(self, auto_save_interval: std::time::Duration) -> Self {
+        _ = auto_save_interval;
+        self
+    }
+
+    /// eframe stores the data in the local storage of the browser, this has no effect.
+    #[must_use]
+    pub fn with_storage(
+        self,
+        factory: impl Fn() -> Box<dyn StorageExt> + Send + Sync + 'static,
+    ) -> Self {
+        _ = factory;
+        self
+    }
+
+    /// eframe stores the data in the local storage of the browser, this has no effect.
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_storage_filename(self, storage_filename: impl Into<std::path::PathBuf>) -> Self {
+        _ = storage_filename;
+        self
+    }
+
+    /// eframe stores the data in the local storage of the browser, this has no effect.
+    #[must_use]
+    pub const fn with_storage_format(self, storage_format: StorageFormat) -> Self {
+        _ = storage_format;
+        self
+    }
+
+    /// eframe stores the data in the local storage of the browser, this has no effect.
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_storage_path(self, storage_path: impl Into<PathBuf>) -> Self {
+        _ = storage_path;
+        self
+    }
+
+    /// eframe stores the data in the local storage of the browser, this has no effect.
+    #[must_use]
+    pub const fn with_storage_file_compression(
+        self,
+        compression: crate::StorageCompression,
+    ) -> Self {
+        _ = compression;
+        self
+    }
+
+    /// eframe stores the data in the local storage of the browser, this has no effect.
+    #[cfg(feature = "storage-compression")]
+    #[must_use]
+    pub const fn with_storage_compression(self, threshold: usize) -> Self {
+        _ = threshold;
+        self
+    }
+
+    /// eframe stores the data in the local storage of the browser, this has no effect.
+    #[must_use]
+    pub const fn with_eframe_storage_import(self, eframe_storage_import: bool) -> Self {
+        _ = eframe_storage_import;
+        self
+    }
+
+    /// eframe stores the data in the local storage of the browser, this has no effect.
+    #[must_use]
+    pub const fn with_portable_storage(self, portable_storage: bool) -> Self {
+        _ = portable_storage;
+        self
+    }
+
+    /// eframe only provides read access to the local storage before the app is created,
+    /// so migrations are not supported on the web and this has no effect.
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_storage_migration(self, migrations: Vec<Box<dyn StorageMigration>>) -> Self {
+        _ = migrations;
+        self
+    }
+
+    /// Sets a callback that is called once the backend is selected.
+    /// On the web, the callback is always called with `Backend::Web` right before eframe is started.
+    #[must_use]
+    pub fn on_backend_selected(mut self, callback: impl FnOnce(Backend) + Send + 'static) -> Self {
+        self.backend_selected_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Custom backends cannot be launched on the web, this has no effect.
+    #[must_use]
+    pub fn register_custom_backend(
+        self,
+        id: u32,
+        launch_fn: impl FnOnce(Self) -> Result<(), Box<dyn Error>> + Send + 'static,
+    ) -> Self {
+        _ = (id, launch_fn);
+        self
+    }
+
+    /// Restricts the backends that may be used.
+    /// `run_app` returns `BackendSelectorError::NoAllowedBackend` if `Backend::Web` is not allowed.
+    #[must_use]
+    pub fn with_allowed_backends(mut self, allowed_backends: &[Backend]) -> Self {
+        self.allowed_backends = Some(allowed_backends.to_vec());
+        self
+    }
+
+    /// There are no command line arguments on the web, this has no effect.
+    #[must_use]
+    pub const fn with_parse_cli_args(self, parse_cli_args: bool) -> Self {
+        _ = parse_cli_args;
+        self
+    }
+
+    /// There is only one backend on the web, this has no effect.
+    #[must_use]
+    pub const fn with_persist_backend_choice(self, persist_backend_choice: bool) -> Self {
+        _ = persist_backend_choice;
+        self
+    }
+
+    /// There is only one backend on the web, this has no effect.
+    #[must_use]
+    pub const fn with_preferred_order(self, preferred_order: &[Backend]) -> Self {
+        _ = preferred_order;
+        self
+    }
+}
+
+impl From<WebOptions> for BackendConfiguration {
+    fn from(value: WebOptions) -> Self {
+        Self {
+            web_options: value,
+            canvas_id: None,
+            backend_selected_callback: None,
+            allowed_backends: None,
+            scale_factor: None,
+            max_fps: None,
+        }
+    }
+}
+
+/// Returns true if the scale factor is in the range (0, 10].
+const fn is_valid_scale_factor(scale_factor: f32) -> bool {
+    scale_factor > 0.0 && scale_factor <= 10.0
+}
+
+/// Run the app using eframe.
+/// On the web, eframe is started asynchronously, so this function returns before the app is created.
+///
+/// # Errors
+/// * If this function is called more than once.
+/// * If the canvas could not be found.
+///
+pub fn run_app<T: App + 'static>(
+    app_name: &str,
+    backend_configuration: impl Into<BackendConfiguration>,
+    mut app_factory: impl FnMut(Context, Option<&dyn Storage>) -> T + 'static,
+) -> Result<(), BackendSelectorError> {
+    try_run_app(app_name, backend_configuration, move |ctx, storage| {
+        Ok(app_factory(ctx, storage))
+    })
+}
+
+/// Run the app using eframe with a fallible app factory.
+///
+/// This function behaves exactly like `run_app`, except that the app factory may fail.
+/// As eframe is started asynchronously, errors of the app factory are only logged.
+///
+/// # Errors
+/// * If this function is called more than once.
+/// * If the canvas could not be found.
+///
+pub fn try_run_app<T: App + 'static>(
+    app_name: &str,
+    backend_configuration: impl Into<BackendConfiguration>,
+    mut app_factory: impl FnMut(
+        Context,
+        Option<&dyn Storage>,
+    ) -> Result<T, Box<dyn Error + Send + Sync>>
+    + 'static,
+) -> Result<(), BackendSelectorError> {
+    if LAUNCHED.swap(true, Relaxed) {
+        return Err(BackendSelectorError::AlreadyLaunched);
+    }
+
+    let config = backend_configuration.into();
+    if let Some(scale_factor) = config.scale_factor
+        && !is_valid_scale_factor(scale_factor)
+    {
+        return Err(BackendSelectorError::InvalidScaleFactor(scale_factor));
+    }
+
+    if config
+        .allowed_backends
+        .as_ref()
+        .is_some_and(|allowed_backends| !allowed_backends.contains(&Backend::Web))
+    {
+        return Err(BackendSelectorError::NoAllowedBackend);
+    }
+
+    set_selection_report(report(
+        Backend::Web,
+        BackendSelectionReason::Default,
+        app_name,
+    ));
+
+    if let Some(callback) = config.backend_selected_callback {
+        callback(Backend::Web);
+    }
+
+    let canvas_id = config
+        .canvas_id
+        .unwrap_or_else(|| DEFAULT_CANVAS_ID.to_string());
+
+    let Some(canvas) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(&canvas_id))
+        .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+    else {
+        return Err(BackendSelectorError::CanvasNotFound(canvas_id));
+    };
+
+    let web_options = config.web_options;
+    let scale_factor = config.scale_factor;
+    let min_frame_time = config
+        .max_fps
+        .map(|max_fps| std::time::Duration::from_secs_f32(1.0 / max_fps));
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = eframe::WebRunner::new()
+            .start(
+                canvas,
+                web_options,
+                Box::new(move |ctx| {
+                    if let Some(scale_factor) = scale_factor {
+                        ctx.egui_ctx.set_pixels_per_point(scale_factor);
+                    }
+
+                    let mut app = app_factory(ctx.egui_ctx.clone(), ctx.storage)?;
+                    app.post_init(&ctx.egui_ctx);
+                    Ok(Box::new(AppWrapper {
+                        app,
+                        state: InteropState {
+                            ctx: ctx.egui_ctx.clone(),
+                            ..InteropState::default()
+                        },
+                        focused: None,
+                        size_in_pixels: None,
+                        min_frame_time,
+                    }))
+                }),
+            )
+            .await;
+
+        if let Err(e) = result {
+            log::error!("Failed to start eframe: {e:?}");
+        }
+    });
+
+    Ok(())
+}